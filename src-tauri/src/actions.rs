@@ -0,0 +1,177 @@
+//! 托盘菜单、前端命令、启动参数和 `smartfolderzen://` 链接共用的操作
+
+use crate::datexpr;
+use crate::error::{AppError, AppResult};
+use crate::folder::{FolderManager, MissingFolderPolicy};
+use crate::journal::Journal;
+use crate::settings::{AppSettings, Profile};
+use chrono::{Datelike, NaiveDate};
+use std::sync::Mutex;
+use tauri::{Manager, Runtime};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// 创建指定日期的文件夹；未指定配置时使用默认配置
+    CreateFolder { profile_id: Option<String>, date: NaiveDate },
+    /// 打开指定日期的文件夹，不存在时打开日期最接近的已有文件夹
+    OpenFolder { profile_id: Option<String>, date: NaiveDate },
+}
+
+impl Action {
+    /// 执行操作，返回创建或打开的文件夹路径
+    pub fn perform<R: Runtime>(&self, app: &tauri::AppHandle<R>) -> AppResult<String> {
+        match self {
+            Action::CreateFolder { profile_id, date } => {
+                let profile = current_profile(app, profile_id.as_deref())?;
+                let path = FolderManager::create_folder_for_date(&profile, date, &Journal::global()?)?;
+                println!("Successfully created folder: {}", path);
+                Ok(path)
+            }
+            Action::OpenFolder { profile_id, date } => {
+                let profile = current_profile(app, profile_id.as_deref())?;
+                let path = FolderManager::resolve_open_path(
+                    &profile,
+                    date,
+                    MissingFolderPolicy::default(),
+                    &Journal::global()?,
+                )?;
+                FolderManager::open_folder_in_explorer(&path)?;
+                println!("Successfully opened folder: {}", path);
+                Ok(path)
+            }
+        }
+    }
+}
+
+/// 取出配置的副本，避免在文件操作期间一直持有设置锁
+fn current_profile<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    profile_id: Option<&str>,
+) -> AppResult<Profile> {
+    let state = app.state::<Mutex<AppSettings>>();
+    let settings = state
+        .lock()
+        .map_err(|_| AppError::configuration("无法获取设置状态"))?;
+    settings.profile(profile_id).cloned()
+}
+
+/// 从启动参数中解析要执行的操作（如桌面快捷方式传入的 `--create-today`）
+///
+/// 支持 `--create-today`、`--open-today`、`--create-date=<日期>`、`--open-date=<日期>`（写法见 [`parse_date`]），
+/// 可配合 `--profile=<id>` 指定配置。第一个参数为程序路径，不认识的参数（如 `--minimized`）被忽略。
+/// 没有操作参数时返回 `Ok(None)`。
+pub fn parse_argv(argv: &[String], today: NaiveDate) -> AppResult<Option<Action>> {
+    let mut profile_id = None;
+    // (是否为创建, 日期)
+    let mut requested: Option<(bool, NaiveDate)> = None;
+
+    for arg in argv.iter().skip(1) {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value)),
+            None => (arg.as_str(), None),
+        };
+
+        let next = match flag {
+            "--profile" => {
+                profile_id = Some(required(flag, value)?.to_string());
+                continue;
+            }
+            "--create-today" => (true, today),
+            "--open-today" => (false, today),
+            "--create-date" => (true, parse_date(required(flag, value)?, today)?),
+            "--open-date" => (false, parse_date(required(flag, value)?, today)?),
+            _ => continue,
+        };
+
+        if requested.replace(next).is_some() {
+            return Err(AppError::configuration("一次只能指定一个操作"));
+        }
+    }
+
+    Ok(requested.map(|(create, date)| {
+        if create {
+            Action::CreateFolder { profile_id, date }
+        } else {
+            Action::OpenFolder { profile_id, date }
+        }
+    }))
+}
+
+fn required<'a>(flag: &str, value: Option<&'a str>) -> AppResult<&'a str> {
+    value
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| AppError::configuration(&format!("参数 {} 缺少取值", flag)))
+}
+
+/// 解析操作中的日期：`YYYY-MM-DD` 或 `yesterday`、`last friday`、`-3d`、`上周五` 等表达式，见 [`datexpr::parse`]
+pub fn parse_date(value: &str, today: NaiveDate) -> AppResult<NaiveDate> {
+    datexpr::parse(value, today).ok_or_else(|| {
+        AppError::configuration(&format!(
+            "无法识别的日期: {}，应为 YYYY-MM-DD 或 yesterday、-3d、上周五 等写法",
+            value
+        ))
+    })
+}
+
+/// 解析 `YYYY-MM` 格式的月份
+pub fn parse_month(value: &str) -> AppResult<(i32, u32)> {
+    NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d")
+        .map(|date| (date.year(), date.month()))
+        .map_err(|_| AppError::configuration(&format!("月份格式无效: {}，应为 YYYY-MM", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        std::iter::once("smart-folder-zen")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_argv() {
+        let today = date(3, 15);
+        let parse = |args: &[&str]| parse_argv(&argv(args), today);
+
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(parse(&["--minimized"]).unwrap(), None);
+        assert_eq!(
+            parse(&["--create-today"]).unwrap(),
+            Some(Action::CreateFolder { profile_id: None, date: today })
+        );
+        assert_eq!(
+            parse(&["--minimized", "--open-today", "--profile=work"]).unwrap(),
+            Some(Action::OpenFolder { profile_id: Some("work".to_string()), date: today })
+        );
+        assert_eq!(
+            parse(&["--open-date=2024-03-01"]).unwrap(),
+            Some(Action::OpenFolder { profile_id: None, date: date(3, 1) })
+        );
+        assert_eq!(
+            parse(&["--create-date=2024-02-29"]).unwrap(),
+            Some(Action::CreateFolder { profile_id: None, date: date(2, 29) })
+        );
+
+        assert_eq!(
+            parse(&["--open-date=yesterday"]).unwrap(),
+            Some(Action::OpenFolder { profile_id: None, date: date(3, 14) })
+        );
+
+        assert_eq!(
+            parse(&["--open-date=上周五"]).unwrap(),
+            Some(Action::OpenFolder { profile_id: None, date: date(3, 8) })
+        );
+
+        assert!(parse(&["--open-date=2024-02-30"]).is_err());
+        assert!(parse(&["--open-date"]).is_err());
+        assert!(parse(&["--profile="]).is_err());
+        assert!(parse(&["--create-today", "--open-today"]).is_err());
+    }
+}
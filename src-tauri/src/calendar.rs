@@ -0,0 +1,220 @@
+use crate::error::{AppError, AppResult};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// 单个 ICS 事件最多展开的天数，防止异常数据生成海量日期
+const MAX_EVENT_DAYS: i64 = 366;
+
+/// 工作日策略：决定哪些日期需要自动创建文件夹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarPolicy {
+    /// 是否仅在工作日自动创建
+    pub workdays_only: bool,
+    /// 常规工作日
+    pub workdays: Vec<Weekday>,
+    /// 节假日，即使落在常规工作日也不创建
+    pub holidays: Vec<NaiveDate>,
+    /// 调休上班日（如周六补班），即使落在周末也创建
+    pub extra_workdays: Vec<NaiveDate>,
+}
+
+/// ICS 导入的目标列表
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarImportTarget {
+    Holidays,
+    ExtraWorkdays,
+}
+
+impl Default for CalendarPolicy {
+    fn default() -> Self {
+        Self {
+            workdays_only: false,
+            workdays: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            holidays: Vec::new(),
+            extra_workdays: Vec::new(),
+        }
+    }
+}
+
+impl CalendarPolicy {
+    /// 按策略判断是否为工作日，调休上班日优先于节假日
+    pub fn is_workday(&self, date: &NaiveDate) -> bool {
+        if self.extra_workdays.contains(date) {
+            return true;
+        }
+        if self.holidays.contains(date) {
+            return false;
+        }
+        self.workdays.contains(&date.weekday())
+    }
+
+    /// 自动创建（启动时、定时）是否应该在这一天进行
+    pub fn allows_auto_create(&self, date: &NaiveDate) -> bool {
+        !self.workdays_only || self.is_workday(date)
+    }
+
+    /// 将导入的日期合并到目标列表，返回新增的数量
+    pub fn merge(&mut self, target: CalendarImportTarget, dates: Vec<NaiveDate>) -> usize {
+        let list = match target {
+            CalendarImportTarget::Holidays => &mut self.holidays,
+            CalendarImportTarget::ExtraWorkdays => &mut self.extra_workdays,
+        };
+
+        // 先整理已有列表，手工编辑的配置中可能有重复日期
+        list.sort();
+        list.dedup();
+        let before = list.len();
+        list.extend(dates);
+        list.sort();
+        list.dedup();
+        list.len() - before
+    }
+}
+
+/// 解析 ICS 日历中所有事件覆盖的日期
+///
+/// 支持全天事件（`DTSTART;VALUE=DATE:20240101`）和带时间的事件（取日期部分），
+/// `DTEND` 按 RFC 5545 视为不包含。重复规则（RRULE）会被忽略。
+pub fn parse_ics(content: &str) -> AppResult<Vec<NaiveDate>> {
+    let mut dates = Vec::new();
+    let mut in_event = false;
+    let mut start: Option<NaiveDate> = None;
+    let mut end: Option<NaiveDate> = None;
+
+    for line in unfold_lines(content) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // 属性名可能带参数，如 DTSTART;VALUE=DATE
+        let name = name.split(';').next().unwrap_or(name).to_ascii_uppercase();
+
+        match (name.as_str(), value.trim()) {
+            ("BEGIN", "VEVENT") => {
+                in_event = true;
+                start = None;
+                end = None;
+            }
+            ("END", "VEVENT") if in_event => {
+                in_event = false;
+                let start = start.ok_or_else(|| {
+                    AppError::configuration("日历文件中的事件缺少 DTSTART")
+                })?;
+                let days = end
+                    .map(|end| (end - start).num_days())
+                    .unwrap_or(1)
+                    .clamp(1, MAX_EVENT_DAYS);
+                dates.extend((0..days).map(|offset| start + Duration::days(offset)));
+            }
+            ("DTSTART", value) if in_event => start = Some(parse_ics_date(value)?),
+            ("DTEND", value) if in_event => end = Some(parse_ics_date(value)?),
+            _ => {}
+        }
+    }
+
+    dates.sort();
+    dates.dedup();
+    Ok(dates)
+}
+
+/// 展开 ICS 的折行：以空格或制表符开头的行属于上一行
+fn unfold_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+fn parse_ics_date(value: &str) -> AppResult<NaiveDate> {
+    // 以 Z 结尾的是 UTC 时间，换算成本地时间后再取日期
+    if let Ok(utc) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(Utc.from_utc_datetime(&utc).with_timezone(&Local).date_naive());
+    }
+    let digits = value.get(..8).unwrap_or(value);
+    NaiveDate::parse_from_str(digits, "%Y%m%d")
+        .map_err(|_| AppError::configuration(&format!("日历文件中的日期无效: {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_is_workday() {
+        let policy = CalendarPolicy {
+            workdays_only: true,
+            holidays: vec![date(10, 1)],
+            extra_workdays: vec![date(10, 12)],
+            ..CalendarPolicy::default()
+        };
+
+        assert!(policy.is_workday(&date(10, 8)));
+        assert!(!policy.is_workday(&date(10, 1)));
+        assert!(!policy.is_workday(&date(10, 13)));
+        // 周六补班
+        assert!(policy.is_workday(&date(10, 12)));
+        assert!(!policy.allows_auto_create(&date(10, 1)));
+        assert!(CalendarPolicy::default().allows_auto_create(&date(10, 1)));
+    }
+
+    #[test]
+    fn test_parse_ics() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20241001\r\n\
+                   DTEND;VALUE=DATE:20241004\r\n\
+                   SUMMARY:国庆\r\n \
+                   节\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART:20240915T120000Z\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+
+        assert_eq!(
+            parse_ics(ics).unwrap(),
+            vec![date(9, 15), date(10, 1), date(10, 2), date(10, 3)]
+        );
+        assert!(parse_ics("BEGIN:VEVENT\nDTSTART:2024\nEND:VEVENT").is_err());
+
+        // UTC 时间按本地时区取日期，浮动时间直接取日期
+        let late = NaiveDateTime::parse_from_str("20241001T233000", "%Y%m%dT%H%M%S").unwrap();
+        assert_eq!(
+            parse_ics_date("20241001T233000Z").unwrap(),
+            Utc.from_utc_datetime(&late).with_timezone(&Local).date_naive()
+        );
+        assert_eq!(parse_ics_date("20241001T233000").unwrap(), date(10, 1));
+    }
+
+    #[test]
+    fn test_merge_dedups() {
+        let mut policy = CalendarPolicy::default();
+        assert_eq!(policy.merge(CalendarImportTarget::Holidays, vec![date(1, 1), date(1, 1)]), 1);
+        assert_eq!(policy.merge(CalendarImportTarget::Holidays, vec![date(1, 1), date(5, 1)]), 1);
+        assert_eq!(policy.holidays, vec![date(1, 1), date(5, 1)]);
+
+        // 已有列表中的重复不算作新增
+        let mut policy = CalendarPolicy {
+            holidays: vec![date(5, 1), date(1, 1), date(5, 1)],
+            ..CalendarPolicy::default()
+        };
+        assert_eq!(policy.merge(CalendarImportTarget::Holidays, vec![date(1, 1)]), 0);
+        assert_eq!(policy.merge(CalendarImportTarget::Holidays, vec![date(10, 1)]), 1);
+        assert_eq!(policy.holidays, vec![date(1, 1), date(5, 1), date(10, 1)]);
+    }
+}
@@ -0,0 +1,390 @@
+//! 命令行模式：不启动窗口，供脚本和 CI 使用
+//!
+//! 与图形界面共用同一份配置文件和 [`FolderManager`]。
+
+use crate::actions;
+use crate::datexpr;
+use crate::error::{AppError, AppResult, ErrorType};
+use crate::folder::{FolderInfo, FolderManager};
+use crate::hooks;
+use crate::journal::Journal;
+use crate::settings::AppSettings;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 参数错误的退出码；其余错误的退出码见 [`ErrorType::exit_code`]
+pub const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+Usage: smart-folder-zen <command> [options]
+
+Commands:
+  today [--print]                     Show today's folder (--print: bare path only)
+  create [--date DATE | --yesterday | --tomorrow]
+                                      Create the folder for a date (default: today)
+  open [--date DATE | --yesterday | --tomorrow]
+                                      Open the folder for a date in the file manager
+  list [--month YYYY-MM]              List existing folders in a month (default: this month)
+  help                                Show this message
+
+Options:
+  --profile <id>    Use the given profile instead of the first one
+  --config <path>   Read settings from this file instead of the app config directory
+  --json            Print machine-readable JSON
+
+Dates:
+  YYYY-MM-DD, or an expression such as yesterday, \"last friday\", -3d, 上周五, 昨天
+
+Exit codes:
+  0 success, 1 unknown error, 2 invalid arguments, 3 file system error,
+  4 invalid path, 5 permission denied, 6 configuration error
+";
+
+const SUBCOMMANDS: &[&str] = &["today", "create", "open", "list", "help"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Today { date: NaiveDate, print: bool },
+    Create { date: NaiveDate },
+    Open { date: NaiveDate },
+    List { year: i32, month: u32 },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub command: Command,
+    pub profile: Option<String>,
+    pub config: Option<PathBuf>,
+    pub json: bool,
+}
+
+/// 第一个参数是子命令时按命令行模式运行，否则启动图形界面
+pub fn is_cli_invocation(args: &[String]) -> bool {
+    args.first().is_some_and(|arg| {
+        SUBCOMMANDS.contains(&arg.as_str()) || arg == "--help" || arg == "-h"
+    })
+}
+
+/// 执行命令行并返回进程退出码
+pub fn run(args: &[String]) -> i32 {
+    attach_parent_console();
+
+    let invocation = match parse_args(args, Local::now().date_naive()) {
+        Ok(invocation) => invocation,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    match Journal::global().and_then(|journal| execute(&invocation, &journal)) {
+        Ok(output) => {
+            println!("{}", output);
+            0
+        }
+        Err(e) => {
+            if invocation.json {
+                eprintln!("{}", serde_json::to_string(&e).unwrap_or_default());
+            } else {
+                eprintln!("error: {}", e);
+            }
+            e.error_type.exit_code()
+        }
+    }
+}
+
+/// 解析命令行参数（不含程序名），`today` 用于计算 `--yesterday` 等相对日期
+pub fn parse_args(args: &[String], today: NaiveDate) -> Result<Invocation, String> {
+    let mut args = args.iter().map(String::as_str);
+    let name = match args.next() {
+        Some("--help" | "-h") | None => "help",
+        Some(name) => name,
+    };
+
+    let mut profile = None;
+    let mut config = None;
+    let mut json = false;
+    let mut print = false;
+    let mut date = None;
+    let mut month = None;
+
+    while let Some(arg) = args.next() {
+        // 同时支持 `--date 2024-03-15` 和 `--date=2024-03-15`
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value)),
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline_value
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} requires a value", flag))
+        };
+
+        match flag {
+            "--profile" => profile = Some(value()?.to_string()),
+            "--config" => config = Some(PathBuf::from(value()?)),
+            "--json" => json = true,
+            "--print" if name == "today" => print = true,
+            "--date" => date = Some(parse_date(value()?, today)?),
+            "--yesterday" => date = Some(today - Duration::days(1)),
+            "--tomorrow" => date = Some(today + Duration::days(1)),
+            "--today" => date = Some(today),
+            "--month" if name == "list" => {
+                month = Some(actions::parse_month(value()?).map_err(|e| e.message)?)
+            }
+            _ => return Err(format!("unexpected argument '{}' for '{}'", arg, name)),
+        }
+    }
+
+    if date.is_some() && !matches!(name, "create" | "open") {
+        return Err(format!("'{}' does not take a date", name));
+    }
+
+    let command = match name {
+        "today" => Command::Today { date: today, print },
+        "create" => Command::Create { date: date.unwrap_or(today) },
+        "open" => Command::Open { date: date.unwrap_or(today) },
+        "list" => {
+            let (year, month) = month.unwrap_or((today.year(), today.month()));
+            Command::List { year, month }
+        }
+        "help" => Command::Help,
+        other => return Err(format!("unknown command '{}'", other)),
+    };
+
+    Ok(Invocation { command, profile, config, json })
+}
+
+fn parse_date(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    datexpr::parse(value, today).ok_or_else(|| {
+        format!("invalid date '{}', expected YYYY-MM-DD or an expression like yesterday or -3d", value)
+    })
+}
+
+/// 执行命令，返回要打印的文本；新建的文件夹记入 `journal`
+///
+/// 标准输出只用于这段文本（`--json` 时为一个 JSON 文档），其他诊断信息都写到标准错误。
+pub fn execute(invocation: &Invocation, journal: &Journal) -> AppResult<String> {
+    if invocation.command == Command::Help {
+        return Ok(USAGE.trim_end().to_string());
+    }
+
+    // 命令行不写配置文件：指定的配置不存在时报错，默认配置不存在时使用默认设置
+    let settings = match &invocation.config {
+        Some(path) => AppSettings::read_from(path)?,
+        None => {
+            let config_path = AppSettings::default_config_path()?;
+            if config_path.exists() {
+                AppSettings::read_from(&config_path)?
+            } else {
+                AppSettings::default()
+            }
+        }
+    };
+    let profile = settings.profile(invocation.profile.as_deref())?;
+
+    let info = |date: NaiveDate| FolderManager::folder_info(profile, &date);
+
+    let folder = match invocation.command {
+        Command::Today { date, print } => {
+            let folder = info(date)?;
+            if print && !invocation.json {
+                return Ok(folder.path);
+            }
+            folder
+        }
+        Command::Create { date } => {
+            FolderManager::create_folder_for_date(profile, &date, journal)?;
+            hooks::wait_all();
+            info(date)?
+        }
+        Command::Open { date } => {
+            let folder = info(date)?;
+            if !folder.exists {
+                return Err(AppError::invalid_path(&format!("文件夹不存在: {}", folder.path)));
+            }
+            FolderManager::open_folder_in_explorer(&folder.path)?;
+            folder
+        }
+        Command::List { year, month } => {
+            let folders = FolderManager::list_month(profile, year, month)?;
+            if invocation.json {
+                return to_json(&folders);
+            }
+            return Ok(folders.iter().map(format_line).collect::<Vec<_>>().join("\n"));
+        }
+        Command::Help => unreachable!(),
+    };
+
+    if invocation.json {
+        return to_json(&folder);
+    }
+    Ok(format_line(&folder))
+}
+
+fn to_json<T: Serialize>(value: &T) -> AppResult<String> {
+    serde_json::to_string_pretty(value)
+        .map_err(|_| AppError::new("无法序列化输出", ErrorType::Unknown))
+}
+
+fn format_line(folder: &FolderInfo) -> String {
+    let state = if folder.exists { "exists" } else { "missing" };
+    format!("{}\t{}\t{}", folder.date, state, folder.path)
+}
+
+/// Windows 发布版使用 GUI 子系统，没有控制台；从终端启动时挂到父进程的控制台上以便输出
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{DateFormat, Profile};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let today = date(3, 15);
+        let parse = |line: &str| parse_args(&args(line), today);
+
+        assert_eq!(parse("today --print").unwrap().command, Command::Today { date: today, print: true });
+        assert_eq!(
+            parse("create --date 2024-03-01 --json").unwrap(),
+            Invocation {
+                command: Command::Create { date: date(3, 1) },
+                profile: None,
+                config: None,
+                json: true,
+            }
+        );
+        assert_eq!(parse("open --yesterday").unwrap().command, Command::Open { date: date(3, 14) });
+        assert_eq!(
+            parse("list --month=2024-02 --profile work").unwrap().profile.as_deref(),
+            Some("work")
+        );
+        assert_eq!(parse("list").unwrap().command, Command::List { year: 2024, month: 3 });
+        assert_eq!(parse("--help").unwrap().command, Command::Help);
+
+        assert_eq!(parse("open --date=-3d").unwrap().command, Command::Open { date: date(3, 12) });
+
+        assert!(parse("create --date 2024-02-30").is_err());
+        assert!(parse("list --month").is_err());
+        assert!(parse("today --yesterday").is_err());
+        assert!(parse("create --print").is_err());
+        assert!(parse("remove").is_err());
+
+        assert!(is_cli_invocation(&args("today")));
+        assert!(!is_cli_invocation(&args("--minimized")));
+        assert!(!is_cli_invocation(&[]));
+    }
+
+    #[test]
+    fn test_create_and_list() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+        let settings = AppSettings {
+            profiles: vec![Profile {
+                folder_path: temp_dir.path().join("daily").to_string_lossy().to_string(),
+                date_format: DateFormat::YYYYMMDD,
+                ..Profile::default()
+            }],
+            ..AppSettings::default()
+        };
+        std::fs::create_dir(temp_dir.path().join("daily")).unwrap();
+        settings.save_to(&config_path).unwrap();
+        let journal = Journal::at(temp_dir.path().join(crate::journal::JOURNAL_FILE));
+
+        let invoke = |line: &str| {
+            let mut invocation = parse_args(&args(line), date(3, 15)).unwrap();
+            invocation.config = Some(config_path.clone());
+            execute(&invocation, &journal)
+        };
+
+        let created = invoke("create --date 2024-03-01").unwrap();
+        assert!(created.starts_with("2024-03-01\texists\t"));
+        invoke("create").unwrap();
+
+        let listed: Vec<serde_json::Value> =
+            serde_json::from_str(&invoke("list --json").unwrap()).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[1]["date"], "2024-03-15");
+        assert_eq!(invoke("list --month 2024-04").unwrap(), "");
+
+        let error = invoke("open --date 2024-03-02").unwrap_err();
+        assert_eq!(error.error_type.exit_code(), 4);
+        assert_eq!(invoke("today --profile missing").unwrap_err().error_type.exit_code(), 6);
+
+        // 指定的配置文件不存在时报错，也不会创建它
+        let mut invocation = parse_args(&args("today"), date(3, 15)).unwrap();
+        invocation.config = Some(temp_dir.path().join("missing.json"));
+        assert_eq!(execute(&invocation, &journal).unwrap_err().error_type.exit_code(), 6);
+        assert!(!temp_dir.path().join("missing.json").exists());
+    }
+
+    /// 在子进程中运行 `create --json`，检查真实的标准输出是一个完整的 JSON 文档
+    #[cfg(unix)]
+    #[test]
+    fn test_create_json_stdout() {
+        const BEGIN: &str = "--- cli stdout begin ---";
+        const END: &str = "--- cli stdout end ---";
+
+        // 子进程：直接执行命令行，前后加上标记，与测试框架自己的输出区分开
+        if let Ok(line) = std::env::var("SFZ_TEST_CLI_ARGS") {
+            println!("{}", BEGIN);
+            let code = run(&line.split('\t').map(String::from).collect::<Vec<_>>());
+            println!("{}", END);
+            std::process::exit(code);
+        }
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+        std::fs::create_dir(temp_dir.path().join("daily")).unwrap();
+        AppSettings {
+            profiles: vec![Profile {
+                folder_path: temp_dir.path().join("daily").to_string_lossy().to_string(),
+                date_format: DateFormat::YYYYMMDD,
+                ..Profile::default()
+            }],
+            ..AppSettings::default()
+        }
+        .save_to(&config_path)
+        .unwrap();
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["cli::tests::test_create_json_stdout", "--exact", "--nocapture", "--test-threads=1"])
+            .env(
+                "SFZ_TEST_CLI_ARGS",
+                format!("create\t--date\t2024-03-15\t--json\t--config\t{}", config_path.display()),
+            )
+            // 操作日志写到临时目录
+            .env("HOME", temp_dir.path())
+            .env("XDG_DATA_HOME", temp_dir.path().join("data"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let (_, rest) = stdout.split_once(BEGIN).unwrap();
+        let (printed, _) = rest.split_once(END).unwrap();
+        let folder: serde_json::Value = serde_json::from_str(printed).unwrap();
+        assert_eq!(folder["date"], "2024-03-15");
+        assert_eq!(folder["exists"], true);
+    }
+}
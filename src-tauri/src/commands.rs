@@ -1,42 +1,83 @@
+use crate::calendar::{self, CalendarImportTarget};
+use crate::diagnostics::StartupDiagnostics;
 use crate::error::AppError;
-use crate::folder::FolderManager;
-use crate::settings::AppSettings;
-use tauri::{Manager, State};
+use crate::folder::{CatalogEntry, FolderManager, MissingFolderPolicy};
+use crate::actions::{self, Action};
+use crate::control_api;
+use crate::hooks::{self, HookRun};
+use crate::journal::{Journal, Operation};
+use crate::overview::{self, DayMeta, MonthOverview};
+use crate::organize::{self, OrganizeOptions, OrganizePlan, OrganizeReport};
+use crate::rename::{self, NamingChanged, RenameOptions, RenamePlan, RenameReport};
+use crate::retention::{self, RetentionPlan, RetentionReport};
+use crate::rules::{self, FileFacts, RuleEvaluation, RuleOutcome};
+use crate::settings::{AppSettings, Profile, SettingsBackup};
+use crate::tray;
+use tauri::{Emitter, Manager, State};
+use std::path::Path;
 use std::sync::Mutex;
 
 pub type SettingsState<'a> = State<'a, Mutex<AppSettings>>;
 
-#[tauri::command]
-pub async fn create_today_folder(
-    settings_state: SettingsState<'_>,
-) -> Result<String, AppError> {
+/// 取出配置的副本后立即释放设置锁，耗时的文件操作期间不阻塞托盘、定时任务和其他命令
+fn profile_snapshot(settings_state: &SettingsState<'_>, profile_id: Option<&str>) -> Result<Profile, AppError> {
     let settings = settings_state.lock().map_err(|_| {
         AppError::configuration("无法获取设置状态")
     })?;
-    
-    FolderManager::create_today_folder(&settings)
+    settings.profile(profile_id).cloned()
+}
+
+#[tauri::command]
+pub async fn create_today_folder(
+    app: tauri::AppHandle,
+    profile_id: Option<String>,
+) -> Result<String, AppError> {
+    let today = chrono::Local::now().date_naive();
+    Action::CreateFolder { profile_id, date: today }.perform(&app)
 }
 
 #[tauri::command]
 pub async fn open_folder_in_explorer(
+    app: tauri::AppHandle,
     path: Option<String>,
-    settings_state: SettingsState<'_>,
+    profile_id: Option<String>,
 ) -> Result<(), AppError> {
-    let folder_path = if let Some(path) = path {
-        path
-    } else {
-        let settings = settings_state.lock().map_err(|_| {
-            AppError::configuration("无法获取设置状态")
-        })?;
-        
-        if FolderManager::today_folder_exists(&settings) {
-            FolderManager::get_today_folder_path(&settings)
-        } else {
-            settings.folder_path.clone()
+    match path {
+        Some(path) => FolderManager::open_folder_in_explorer(&path),
+        None => {
+            let today = chrono::Local::now().date_naive();
+            Action::OpenFolder { profile_id, date: today }.perform(&app).map(|_| ())
         }
-    };
+    }
+}
 
-    FolderManager::open_folder_in_explorer(&folder_path)
+/// 把日期或 `yesterday`、`last friday`、`-3d`、`上周五` 等表达式解析为具体日期，供界面预览
+#[tauri::command]
+pub async fn resolve_date(expression: String) -> Result<chrono::NaiveDate, AppError> {
+    actions::parse_date(&expression, chrono::Local::now().date_naive())
+}
+
+/// 打开日期或表达式对应的文件夹；文件夹不存在时按 `missing` 处理，默认打开最接近的已有文件夹
+#[tauri::command]
+pub async fn open_date(
+    expression: String,
+    profile_id: Option<String>,
+    missing: Option<MissingFolderPolicy>,
+    settings_state: SettingsState<'_>,
+) -> Result<OpenedDate, AppError> {
+    let date = actions::parse_date(&expression, chrono::Local::now().date_naive())?;
+    let profile = profile_snapshot(&settings_state, profile_id.as_deref())?;
+
+    let path = FolderManager::resolve_open_path(&profile, &date, missing.unwrap_or_default(), &Journal::global()?)?;
+    FolderManager::open_folder_in_explorer(&path)?;
+    println!("Successfully opened folder: {}", path);
+    Ok(OpenedDate { date, path })
+}
+
+#[derive(serde::Serialize)]
+pub struct OpenedDate {
+    pub date: chrono::NaiveDate,
+    pub path: String,
 }
 
 #[tauri::command]
@@ -53,18 +94,239 @@ pub async fn save_settings(
     settings_state: SettingsState<'_>,
 ) -> Result<(), AppError> {
     // 验证新设置
-    settings.validate_path()?;
+    settings.validate_paths()?;
+    settings.validate()?;
+    
+    // 先锁住状态再写文件，配置监听读到的新内容总能与状态对上
+    let mut state = settings_state.lock().map_err(|_| {
+        AppError::configuration("无法更新设置状态")
+    })?;
     
     // 保存到文件
     settings.save(&app)?;
     
+    // 命名格式变了的配置，提示界面把已有文件夹改为新名称
+    for profile in &settings.profiles {
+        let previous = state.profiles.iter().find(|previous| previous.id == profile.id);
+        if let Some(previous) = previous.filter(|previous| previous.naming_levels() != profile.naming_levels()) {
+            let _ = app.emit(rename::NAMING_CHANGED_EVENT, NamingChanged {
+                profile_id: profile.id.clone(),
+                from_levels: previous.naming_levels().to_vec(),
+            });
+        }
+    }
+
     // 更新内存中的状态
+    tray::refresh(&app, &settings);
+    *state = settings.clone();
+    // 先释放设置锁：重启控制接口会等待其线程结束，而该线程处理请求时需要这把锁
+    drop(state);
+    control_api::sync(&app, &settings);
+    
+    Ok(())
+}
+
+/// 试算归档规则：显示哪些规则会匹配这个文件，以及它会被移到哪里
+#[tauri::command]
+pub async fn test_rules(
+    path: String,
+    settings_state: SettingsState<'_>,
+) -> Result<RuleTestResult, AppError> {
+    let settings = settings_state.lock().map_err(|_| {
+        AppError::configuration("无法获取设置状态")
+    })?;
+
+    let evaluation = rules::evaluate(&settings.rules, &FileFacts::from_path(Path::new(&path)))?;
+    let destination = match &evaluation.outcome {
+        RuleOutcome::Move { subfolder } => {
+            let profile = settings.profile(settings.inbox.profile_id.as_deref())?;
+            let today = chrono::Local::now().date_naive();
+            let folder = FolderManager::folder_path_for_date(profile, &today)?.join(subfolder);
+            Path::new(&path)
+                .file_name()
+                .map(|name| folder.join(name).to_string_lossy().to_string())
+        }
+        RuleOutcome::Ignore => None,
+    };
+
+    Ok(RuleTestResult { evaluation, destination })
+}
+
+#[derive(serde::Serialize)]
+pub struct RuleTestResult {
+    pub evaluation: RuleEvaluation,
+    /// 文件会被移到的位置（不考虑重名），被忽略时为空
+    pub destination: Option<String>,
+}
+
+/// 预览按日期整理的移动计划，不修改任何文件
+#[tauri::command]
+pub async fn plan_organize(
+    options: OrganizeOptions,
+    settings_state: SettingsState<'_>,
+) -> Result<OrganizePlan, AppError> {
+    let profile = profile_snapshot(&settings_state, options.profile_id.as_deref())?;
+    organize::plan(&profile, &options)
+}
+
+/// 按日期整理目录，执行过程中发出 organize-progress 事件
+#[tauri::command]
+pub async fn run_organize(
+    app: tauri::AppHandle,
+    options: OrganizeOptions,
+    settings_state: SettingsState<'_>,
+) -> Result<OrganizeReport, AppError> {
+    // 重新生成计划，避免按过期的预览移动文件
+    let profile = profile_snapshot(&settings_state, options.profile_id.as_deref())?;
+    let plan = organize::plan(&profile, &options)?;
+
+    let journal = Journal::global()?;
+    organize::execute(&plan, Path::new(&options.source_dir), &journal, |progress| {
+        let _ = app.emit(organize::ORGANIZE_PROGRESS_EVENT, progress);
+    })
+}
+
+/// 操作日志，最新的在前
+#[tauri::command]
+pub async fn list_operations(limit: Option<usize>) -> Result<Vec<Operation>, AppError> {
+    let operations = Journal::global()?.operations()?;
+    Ok(operations.into_iter().rev().take(limit.unwrap_or(100)).collect())
+}
+
+/// 撤销操作日志中的一个操作
+#[tauri::command]
+pub async fn undo_operation(operation_id: String) -> Result<Operation, AppError> {
+    Journal::global()?.undo(&operation_id)
+}
+
+/// 撤销最近一个尚未撤销的操作
+#[tauri::command]
+pub async fn undo_last() -> Result<Operation, AppError> {
+    Journal::global()?.undo_last()
+}
+
+/// 预览会被归档的旧文件夹，不修改任何文件
+#[tauri::command]
+pub async fn plan_retention(settings_state: SettingsState<'_>) -> Result<RetentionPlan, AppError> {
+    let settings = settings_state
+        .lock()
+        .map_err(|_| AppError::configuration("无法获取设置状态"))?
+        .clone();
+    retention::plan(&settings, chrono::Local::now().date_naive())
+}
+
+/// 立即归档旧文件夹，不受定时开关影响
+#[tauri::command]
+pub async fn run_retention(settings_state: SettingsState<'_>) -> Result<RetentionReport, AppError> {
+    let settings = settings_state
+        .lock()
+        .map_err(|_| AppError::configuration("无法获取设置状态"))?
+        .clone();
+    retention::run(&settings, chrono::Local::now().date_naive(), &Journal::global()?)
+}
+
+/// 预览把旧命名格式的文件夹改为当前格式的重命名计划，不修改任何文件
+#[tauri::command]
+pub async fn plan_rename_folders(
+    options: RenameOptions,
+    settings_state: SettingsState<'_>,
+) -> Result<RenamePlan, AppError> {
+    let profile = profile_snapshot(&settings_state, options.profile_id.as_deref())?;
+    rename::plan(&profile, &options)
+}
+
+/// 把旧命名格式的文件夹改为当前格式
+#[tauri::command]
+pub async fn run_rename_folders(
+    options: RenameOptions,
+    settings_state: SettingsState<'_>,
+) -> Result<RenameReport, AppError> {
+    let profile = profile_snapshot(&settings_state, options.profile_id.as_deref())?;
+
+    // 重新生成计划，避免按过期的预览重命名
+    let plan = rename::plan(&profile, &options)?;
+    rename::execute(&plan, &profile, options.include_ambiguous, &Journal::global()?)
+}
+
+/// 列出基础路径中的全部日期文件夹；名称缺少年份时以 `reference`（为空时按修改时间）推断
+#[tauri::command]
+pub async fn scan_folders(
+    profile_id: Option<String>,
+    reference: Option<chrono::NaiveDate>,
+    settings_state: SettingsState<'_>,
+) -> Result<Vec<CatalogEntry>, AppError> {
+    let profile = profile_snapshot(&settings_state, profile_id.as_deref())?;
+    FolderManager::scan(&profile, reference)
+}
+
+/// 一个月中每天的文件夹概况，供日历视图使用
+#[tauri::command]
+pub async fn get_month_overview(
+    profile_id: Option<String>,
+    year: i32,
+    month: u32,
+    settings_state: SettingsState<'_>,
+) -> Result<MonthOverview, AppError> {
+    let (profile, calendar) = {
+        let settings = settings_state.lock().map_err(|_| {
+            AppError::configuration("无法获取设置状态")
+        })?;
+        (settings.profile(profile_id.as_deref())?.clone(), settings.calendar.clone())
+    };
+    overview::month(&profile, &calendar, year, month)
+}
+
+/// 给某一天的文件夹设置标签和备注，文件夹必须已存在
+#[tauri::command]
+pub async fn set_day_meta(
+    profile_id: Option<String>,
+    date: chrono::NaiveDate,
+    meta: DayMeta,
+    settings_state: SettingsState<'_>,
+) -> Result<(), AppError> {
+    let profile = profile_snapshot(&settings_state, profile_id.as_deref())?;
+    overview::write_meta(&overview::day_folder(&profile, &date)?, &meta)
+}
+
+/// 最近的钩子执行记录，最新的在前
+#[tauri::command]
+pub async fn get_hook_log() -> Result<Vec<HookRun>, AppError> {
+    Ok(hooks::recent_runs())
+}
+
+/// 列出可供回滚的历史配置
+#[tauri::command]
+pub async fn list_settings_backups(app: tauri::AppHandle) -> Result<Vec<SettingsBackup>, AppError> {
+    AppSettings::list_backups(&app)
+}
+
+/// 回滚到指定的历史配置，当前配置会进入备份
+#[tauri::command]
+pub async fn restore_settings_backup(
+    app: tauri::AppHandle,
+    index: usize,
+    settings_state: SettingsState<'_>,
+) -> Result<AppSettings, AppError> {
     let mut state = settings_state.lock().map_err(|_| {
         AppError::configuration("无法更新设置状态")
     })?;
-    *state = settings;
-    
-    Ok(())
+
+    let settings = AppSettings::restore_backup(&app, index)?;
+    tray::refresh(&app, &settings);
+    *state = settings.clone();
+    // 同 save_settings，重启控制接口前先释放设置锁
+    drop(state);
+    control_api::sync(&app, &settings);
+
+    Ok(settings)
+}
+
+/// 启动时发生的问题（如配置文件损坏后的恢复），供前端提示用户
+#[tauri::command]
+pub async fn get_startup_diagnostics(
+    diagnostics: State<'_, StartupDiagnostics>,
+) -> Result<StartupDiagnostics, AppError> {
+    Ok(diagnostics.inner().clone())
 }
 
 #[tauri::command]
@@ -74,22 +336,52 @@ pub async fn validate_folder_path(path: String) -> Result<(), AppError> {
 
 #[tauri::command]
 pub async fn get_today_folder_status(
+    profile_id: Option<String>,
     settings_state: SettingsState<'_>,
 ) -> Result<FolderStatus, AppError> {
     let settings = settings_state.lock().map_err(|_| {
         AppError::configuration("无法获取设置状态")
     })?;
+    let profile = settings.profile(profile_id.as_deref())?;
     
-    let exists = FolderManager::today_folder_exists(&settings);
-    let path = FolderManager::get_today_folder_path(&settings);
+    let exists = FolderManager::today_folder_exists(profile);
+    let path = FolderManager::get_today_folder_path(profile)?;
+    let is_workday = settings.calendar.is_workday(&chrono::Local::now().date_naive());
     
-    Ok(FolderStatus { exists, path })
+    Ok(FolderStatus { profile_id: profile.id.clone(), exists, path, is_workday })
 }
 
 #[derive(serde::Serialize)]
 pub struct FolderStatus {
+    pub profile_id: String,
     pub exists: bool,
     pub path: String,
+    pub is_workday: bool,
+}
+
+/// 从本地 .ics 文件导入节假日或调休上班日，返回新增的日期数量
+#[tauri::command]
+pub async fn import_holiday_calendar(
+    app: tauri::AppHandle,
+    path: String,
+    target: CalendarImportTarget,
+    settings_state: SettingsState<'_>,
+) -> Result<usize, AppError> {
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::file_system(&format!("无法读取日历文件: {}", e)))?;
+    let dates = calendar::parse_ics(&content)?;
+
+    let mut settings = settings_state.lock().map_err(|_| {
+        AppError::configuration("无法更新设置状态")
+    })?;
+    let mut updated = settings.clone();
+    let added = updated.calendar.merge(target, dates);
+    updated.save(&app)?;
+
+    tray::refresh(&app, &updated);
+    *settings = updated;
+
+    Ok(added)
 }
 
 #[tauri::command]
@@ -0,0 +1,158 @@
+use crate::control_api;
+use crate::error::{AppError, AppResult};
+use crate::settings::AppSettings;
+use crate::tray;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+/// 编辑器保存时往往连续产生多次事件，静默这么久后才重新加载
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 外部修改已生效，负载为新的设置
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+/// 外部修改无效被拒绝，负载为 `AppError`
+pub const SETTINGS_RELOAD_FAILED_EVENT: &str = "settings-reload-failed";
+
+/// 在后台监听配置文件，被手动编辑或同步工具改写时重新加载
+pub fn start(app: tauri::AppHandle) {
+    let config_path = match AppSettings::get_config_path(&app) {
+        Ok(config_path) => config_path,
+        Err(e) => {
+            eprintln!("Config watcher: {}", e);
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        let result = watch(&config_path, || {
+            let state = app.state::<Mutex<AppSettings>>();
+            match reload(&state, &config_path) {
+                Ok(Some(settings)) => {
+                    println!("Settings reloaded from {}", config_path.display());
+                    tray::refresh(&app, &settings);
+                    control_api::sync(&app, &settings);
+                    let _ = app.emit(SETTINGS_CHANGED_EVENT, settings);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("Rejected external settings change: {}", e);
+                    let _ = app.emit(SETTINGS_RELOAD_FAILED_EVENT, e);
+                }
+            }
+        });
+
+        if let Err(e) = result {
+            eprintln!("Config watcher stopped: {}", e);
+        }
+    });
+}
+
+/// 监听配置文件所在目录，配置文件有变化时调用 `on_change`
+///
+/// 原子写入会用新文件替换旧文件，直接监听文件本身在第一次保存后就会失效，因此监听目录。
+pub fn watch(config_path: &Path, mut on_change: impl FnMut()) -> notify::Result<()> {
+    let dir = config_path
+        .parent()
+        .ok_or_else(|| notify::Error::generic("配置文件没有上级目录"))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    while let Ok(event) = rx.recv() {
+        let touches_config = match event {
+            Ok(event) => {
+                !matches!(event.kind, EventKind::Access(_))
+                    && event.paths.iter().any(|path| path == config_path)
+            }
+            Err(e) => {
+                eprintln!("Config watcher error: {}", e);
+                false
+            }
+        };
+        if !touches_config {
+            continue;
+        }
+
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        on_change();
+    }
+
+    Ok(())
+}
+
+/// 将配置文件的当前内容应用到内存状态
+///
+/// 内容无效时返回错误且不修改状态；与当前状态相同（如本程序自己刚保存过）时返回 `None`。
+pub fn reload(state: &Mutex<AppSettings>, config_path: &Path) -> AppResult<Option<AppSettings>> {
+    let settings = AppSettings::reload_from(config_path)?;
+
+    let mut current = state
+        .lock()
+        .map_err(|_| AppError::configuration("无法更新设置状态"))?;
+    if serde_json::to_value(&*current).ok() == serde_json::to_value(&settings).ok() {
+        return Ok(None);
+    }
+
+    *current = settings.clone();
+    Ok(Some(settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_reload_applies_valid_and_rejects_invalid_edits() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+        let original = AppSettings::default();
+        original.save_to(&config_path).unwrap();
+        let state = Mutex::new(original.clone());
+
+        // 内容未变（如本程序自己保存）
+        assert!(reload(&state, &config_path).unwrap().is_none());
+
+        let mut edited = serde_json::to_value(&original).unwrap();
+        edited["auto_start"] = false.into();
+        fs::write(&config_path, edited.to_string()).unwrap();
+        let applied = reload(&state, &config_path).unwrap().unwrap();
+        assert!(!applied.auto_start);
+        assert!(!state.lock().unwrap().auto_start);
+
+        // 语法错误和校验失败都不会污染状态
+        fs::write(&config_path, "{ \"profiles\": [").unwrap();
+        assert!(reload(&state, &config_path).is_err());
+        edited["profiles"] = serde_json::json!([]);
+        fs::write(&config_path, edited.to_string()).unwrap();
+        assert!(reload(&state, &config_path).is_err());
+        assert_eq!(state.lock().unwrap().profiles.len(), 1);
+        assert!(!state.lock().unwrap().auto_start);
+    }
+
+    #[test]
+    fn test_watch_reports_changes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+        fs::write(&config_path, "{}").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let watched = config_path.clone();
+        std::thread::spawn(move || {
+            let _ = watch(&watched, || {
+                let _ = tx.send(());
+            });
+        });
+
+        // 等待监听就绪后再改写；其他文件的变化不应触发
+        std::thread::sleep(Duration::from_millis(200));
+        fs::write(temp_dir.path().join("other.json"), "{}").unwrap();
+        fs::write(&config_path, "{\"auto_start\": false}").unwrap();
+
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
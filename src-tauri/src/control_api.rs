@@ -0,0 +1,362 @@
+//! 本地控制接口：仅监听 127.0.0.1 的 HTTP 服务，供编辑器和自动化脚本查询、创建当天文件夹
+//!
+//! 每个请求都需要带上 `Authorization: Bearer <token>`，令牌保存在配置目录的
+//! `control-api-token` 文件中。所有响应均为 JSON，出错时返回 `AppError`。
+//!
+//! | 方法 | 路径 | 说明 |
+//! |------|------|------|
+//! | GET  | `/status` | 版本、今天是否工作日、各配置今天的文件夹 |
+//! | GET  | `/today?profile=` | 今天的文件夹 |
+//! | POST | `/create?profile=&date=` | 创建文件夹 |
+//! | POST | `/open?profile=&date=` | 在文件管理器中打开文件夹 |
+//! | GET  | `/list?profile=&month=YYYY-MM` | 某月已存在的文件夹 |
+
+use crate::actions;
+use crate::error::{AppError, AppResult, ErrorType};
+use crate::folder::{FolderInfo, FolderManager, MissingFolderPolicy};
+use crate::fs_util;
+use crate::journal::Journal;
+use crate::settings::AppSettings;
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tauri::{Manager, Url};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// 令牌文件名，位于配置目录
+pub const TOKEN_FILE: &str = "control-api-token";
+
+/// 控制接口设置，默认关闭
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlApiSettings {
+    pub enabled: bool,
+    /// 监听端口，只绑定 127.0.0.1
+    pub port: u16,
+}
+
+impl Default for ControlApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47615,
+        }
+    }
+}
+
+/// 读取当前设置；GUI 中从托管状态读取，测试中可直接提供
+pub type SettingsProvider = Arc<dyn Fn() -> AppResult<AppSettings> + Send + Sync>;
+
+/// 运行中的控制接口，丢弃时停止监听
+pub struct ControlServer {
+    server: Arc<Server>,
+    port: u16,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ControlServer {
+    /// 在 127.0.0.1 上开始监听；端口为 0 时由系统分配，新建的文件夹记入 `journal`
+    pub fn start(port: u16, token: String, settings: SettingsProvider, journal: Journal) -> AppResult<Self> {
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| AppError::configuration(&format!("无法启动控制接口: {}", e)))?;
+        let server = Arc::new(server);
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|addr| addr.port())
+            .unwrap_or(port);
+
+        let listener = Arc::clone(&server);
+        let thread = std::thread::spawn(move || {
+            for request in listener.incoming_requests() {
+                handle(request, &token, &settings, &journal);
+            }
+        });
+
+        println!("Control API listening on 127.0.0.1:{}", port);
+        Ok(Self {
+            server,
+            port,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// GUI 中运行的控制接口
+#[derive(Default)]
+pub struct ControlApiState(Mutex<Option<ControlServer>>);
+
+/// 按设置启动、重启或停止控制接口；设置变更后调用
+pub fn sync(app: &tauri::AppHandle, settings: &AppSettings) {
+    let state = app.state::<ControlApiState>();
+    let Ok(mut running) = state.0.lock() else {
+        eprintln!("Control API: failed to lock state");
+        return;
+    };
+
+    let wanted = &settings.control_api;
+    if !wanted.enabled {
+        *running = None;
+        return;
+    }
+    if running.as_ref().is_some_and(|server| server.port() == wanted.port) {
+        return;
+    }
+
+    // 先停掉旧的服务再绑定，端口不变时也能重新启动
+    *running = None;
+    let result = AppSettings::get_config_path(app)
+        .and_then(|config_path| load_or_create_token(&config_path))
+        .and_then(|token| Ok((token, Journal::global()?)))
+        .and_then(|(token, journal)| {
+            let handle = app.clone();
+            let provider: SettingsProvider = Arc::new(move || {
+                let state = handle.state::<Mutex<AppSettings>>();
+                let settings = state
+                    .lock()
+                    .map_err(|_| AppError::configuration("无法获取设置状态"))?;
+                Ok(settings.clone())
+            });
+            ControlServer::start(wanted.port, token, provider, journal)
+        });
+
+    match result {
+        Ok(server) => *running = Some(server),
+        Err(e) => eprintln!("Failed to start control API: {}", e),
+    }
+}
+
+/// 读取配置目录中的令牌，不存在时生成一个新的
+pub fn load_or_create_token(config_path: &Path) -> AppResult<String> {
+    let token_path = config_path.with_file_name(TOKEN_FILE);
+    if let Ok(token) = fs::read_to_string(&token_path) {
+        let token = token.trim();
+        if !token.is_empty() {
+            return Ok(token.to_string());
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)
+        .map_err(|e| AppError::configuration(&format!("无法生成控制接口令牌: {}", e)))?;
+    let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    if let Some(dir) = token_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs_util::write_atomic(&token_path, token.as_bytes())
+        .map_err(|_| AppError::configuration("无法保存控制接口令牌"))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&token_path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    Ok(token)
+}
+
+#[derive(Serialize)]
+struct Status {
+    version: &'static str,
+    today: NaiveDate,
+    is_workday: bool,
+    profiles: Vec<FolderInfo>,
+}
+
+/// 按常数时间比较令牌，比较耗时不随第一个不同字节的位置变化
+fn token_matches(given: &str, token: &str) -> bool {
+    let (given, token) = (given.as_bytes(), token.as_bytes());
+    given.len() == token.len() && given.iter().zip(token).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn handle(request: Request, token: &str, settings: &SettingsProvider, journal: &Journal) {
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header
+                .value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .is_some_and(|given| token_matches(given, token))
+    });
+
+    let result = if authorized {
+        settings().and_then(|settings| route(request.method(), request.url(), &settings, journal))
+    } else {
+        Err(AppError::permission_denied("缺少或错误的访问令牌"))
+    };
+
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err(e) => {
+            let status = match (&e.error_type, authorized) {
+                (_, false) => 401,
+                (ErrorType::Configuration, _) => 400,
+                (ErrorType::InvalidPath, _) => 404,
+                (ErrorType::PermissionDenied, _) => 403,
+                (ErrorType::FileSystem | ErrorType::Unknown, _) => 500,
+            };
+            (status, serde_json::to_value(&e).unwrap_or_default())
+        }
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json; charset=utf-8")
+        .expect("static header is valid");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        eprintln!("Control API: failed to respond: {}", e);
+    }
+}
+
+fn route(method: &Method, url: &str, settings: &AppSettings, journal: &Journal) -> AppResult<serde_json::Value> {
+    let url = Url::parse(&format!("http://127.0.0.1{}", url))
+        .map_err(|_| AppError::configuration("请求地址无效"))?;
+    let today = Local::now().date_naive();
+
+    let mut profile_id = None;
+    let mut date = today;
+    let mut month = (today.year(), today.month());
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "profile" => profile_id = Some(value.into_owned()),
+            "date" => date = actions::parse_date(&value, today)?,
+            "month" => month = actions::parse_month(&value)?,
+            _ => return Err(AppError::configuration(&format!("无效的参数: {}", key))),
+        }
+    }
+    let profile = || settings.profile(profile_id.as_deref());
+
+    let body = match (method, url.path()) {
+        (Method::Get, "/status") => to_value(Status {
+            version: env!("CARGO_PKG_VERSION"),
+            today,
+            is_workday: settings.calendar.is_workday(&today),
+            profiles: settings
+                .profiles
+                .iter()
+                .map(|profile| FolderManager::folder_info(profile, &today))
+                .collect::<AppResult<_>>()?,
+        }),
+        (Method::Get, "/today") => to_value(FolderManager::folder_info(profile()?, &today)?),
+        (Method::Post, "/create") => {
+            let profile = profile()?;
+            FolderManager::create_folder_for_date(profile, &date, journal)?;
+            to_value(FolderManager::folder_info(profile, &date)?)
+        }
+        (Method::Post, "/open") => {
+            let path = FolderManager::resolve_open_path(profile()?, &date, MissingFolderPolicy::default(), journal)?;
+            FolderManager::open_folder_in_explorer(&path)?;
+            serde_json::json!({ "path": path })
+        }
+        (Method::Get, "/list") => to_value(FolderManager::list_month(profile()?, month.0, month.1)?),
+        (_, path) => return Err(AppError::invalid_path(&format!("未知的接口: {} {}", method, path))),
+    };
+
+    Ok(body)
+}
+
+fn to_value<T: Serialize>(value: T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{DateFormat, Profile};
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    /// 发送一个 HTTP 请求，返回状态码和解析后的 JSON
+    fn request(port: u16, method: &str, path: &str, token: Option<&str>) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let auth = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+            method, path, auth
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1;
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_control_api_over_socket() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let settings = AppSettings {
+            profiles: vec![Profile {
+                id: "work".to_string(),
+                folder_path: temp_dir.path().to_string_lossy().to_string(),
+                date_format: DateFormat::YYYYMMDD,
+                ..Profile::default()
+            }],
+            ..AppSettings::default()
+        };
+
+        let config_path = temp_dir.path().join("config").join("settings.json");
+        let token = load_or_create_token(&config_path).unwrap();
+        assert_eq!(token.len(), 64);
+        assert_eq!(load_or_create_token(&config_path).unwrap(), token);
+
+        let journal = Journal::at(temp_dir.path().join("config").join(crate::journal::JOURNAL_FILE));
+        let server = ControlServer::start(
+            0,
+            token.clone(),
+            Arc::new(move || Ok(settings.clone())),
+            journal.clone(),
+        )
+        .unwrap();
+        let port = server.port();
+        // 长度相同、只差最后一个字符的令牌同样被拒绝
+        let near_miss = format!("{}{}", &token[..63], if token.ends_with('0') { '1' } else { '0' });
+        assert_eq!(request(port, "GET", "/status", Some(&near_miss)).0, 401);
+        let token = Some(token.as_str());
+
+        assert_eq!(request(port, "GET", "/status", None).0, 401);
+        assert_eq!(request(port, "GET", "/status", Some("wrong")).0, 401);
+
+        let (status, body) = request(port, "GET", "/status", token);
+        assert_eq!(status, 200);
+        assert_eq!(body["profiles"][0]["exists"], false);
+
+        let (status, body) = request(port, "POST", "/create?profile=work&date=2024-03-15", token);
+        assert_eq!(status, 200);
+        assert_eq!(body["exists"], true);
+        assert!(temp_dir.path().join("2024-03-15").is_dir());
+        assert_eq!(journal.operations().unwrap().len(), 1);
+
+        let (status, body) = request(port, "GET", "/list?month=2024-03", token);
+        assert_eq!(status, 200);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["date"], "2024-03-15");
+
+        let (status, body) = request(port, "GET", "/today?profile=home", token);
+        assert_eq!(status, 400);
+        assert_eq!(body["error_type"], "Configuration");
+        assert_eq!(request(port, "GET", "/list?month=March", token).0, 400);
+        assert_eq!(request(port, "DELETE", "/today", token).0, 404);
+    }
+}
@@ -0,0 +1,298 @@
+//! 日期表达式：把 `2024-03-15`、`yesterday`、`last friday`、`-3d`、`上周五`、`三天前` 这类写法解析为具体日期
+//!
+//! 相对日期都以传入的 `today` 为准，便于测试；一周从周一开始。
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// `-3d`、`+2w`、`-1m`，省略单位时按天
+static OFFSET: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([+-])\s*(\d+)\s*([dwm]?)$").expect("static regex is valid"));
+
+/// `3 days ago`、`in 2 weeks`
+static ENGLISH_OFFSET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:(in) )?(\d+|a|an|one|two|three) (day|week|month)s?(?: (ago))?$").expect("static regex is valid")
+});
+
+/// `last friday`、`next mon`、`friday`
+static ENGLISH_WEEKDAY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:(last|next|this) )?([a-z]+)$").expect("static regex is valid"));
+
+/// `3天前`、`两周后`、`1个月前`
+static CHINESE_OFFSET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([0-9]+|[一二两三四五六七八九十]+)\s*(天|日|周|个星期|星期|个礼拜|礼拜|个月|月)\s*(前|后|以前|以后|之前|之后)$")
+        .expect("static regex is valid")
+});
+
+/// `上周五`、`下星期一`、`本周日`、`周三`
+static CHINESE_WEEKDAY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(上上|上|下下|下|本|这)?(?:个)?(周|星期|礼拜)([一二三四五六日天七])$").expect("static regex is valid")
+});
+
+/// `3月15日`、`2024年3月15号`
+static CHINESE_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:(\d{4})\s*年\s*)?(\d{1,2})\s*月\s*(\d{1,2})\s*[日号]?$").expect("static regex is valid")
+});
+
+/// 带年份的日期写法
+const ABSOLUTE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d", "%Y%m%d"];
+
+/// 解析日期表达式，无法识别或日期无效时返回 `None`
+///
+/// - 具体日期：`2024-03-15`、`2024/3/15`、`20240315`、`2024年3月15日`；`03-15`、`3月15日` 取今年
+/// - 关键词：`today`/`今天`、`yesterday`/`昨天`、`tomorrow`/`明天`，以及 `前天`、`后天`、`大前天`、`大后天`
+/// - 偏移：`-3d`、`+1w`、`-1m`、`3 days ago`、`in 2 weeks`、`3天前`、`两周后`
+/// - 星期：`last friday` 是今天之前最近的周五，`next friday` 是今天之后最近的周五，
+///   `friday` 和 `this friday` 是本周的周五；`上周五`、`下周五`、`本周五`（`周五`）按自然周计算
+pub fn parse(expression: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let normalized = expression.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let value = normalized.as_str();
+
+    let keyword = match value {
+        "today" | "今天" | "今日" => Some(0),
+        "yesterday" | "昨天" | "昨日" => Some(-1),
+        "tomorrow" | "明天" | "明日" => Some(1),
+        "day before yesterday" | "the day before yesterday" | "前天" => Some(-2),
+        "day after tomorrow" | "the day after tomorrow" | "后天" => Some(2),
+        "大前天" => Some(-3),
+        "大后天" => Some(3),
+        _ => None,
+    };
+    if let Some(days) = keyword {
+        return shift(today, days, 'd');
+    }
+
+    absolute_date(value, today)
+        .or_else(|| offset(value, today))
+        .or_else(|| english_weekday(value, today))
+        .or_else(|| chinese_weekday(value, today))
+}
+
+fn absolute_date(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Some(date) = ABSOLUTE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+    {
+        return Some(date);
+    }
+    if let Some(captures) = CHINESE_DATE.captures(value) {
+        let year = match captures.get(1) {
+            Some(year) => year.as_str().parse().ok()?,
+            None => today.year(),
+        };
+        return NaiveDate::from_ymd_opt(year, captures[2].parse().ok()?, captures[3].parse().ok()?);
+    }
+
+    // 只有月日时取今年
+    let (month, day) = value.split_once('-')?;
+    if month.len() > 2 || day.len() > 2 {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(today.year(), month.parse().ok()?, day.parse().ok()?)
+}
+
+fn offset(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Some(captures) = OFFSET.captures(value) {
+        let amount: i64 = captures[2].parse().ok()?;
+        let sign = if &captures[1] == "-" { -1 } else { 1 };
+        let unit = captures[3].chars().next().unwrap_or('d');
+        return shift(today, sign * amount, unit);
+    }
+
+    if let Some(captures) = ENGLISH_OFFSET.captures(value) {
+        // 必须是 `in …` 或 `… ago` 之一
+        let (future, past) = (captures.get(1).is_some(), captures.get(4).is_some());
+        if future == past {
+            return None;
+        }
+        let amount = match &captures[2] {
+            "a" | "an" | "one" => 1,
+            "two" => 2,
+            "three" => 3,
+            digits => digits.parse().ok()?,
+        };
+        let unit = captures[3].chars().next()?;
+        return shift(today, if past { -amount } else { amount }, unit);
+    }
+
+    let captures = CHINESE_OFFSET.captures(value)?;
+    let amount = chinese_number(&captures[1])?;
+    let unit = match &captures[2] {
+        "天" | "日" => 'd',
+        "个月" | "月" => 'm',
+        _ => 'w',
+    };
+    let sign = if captures[3].ends_with('前') { -1 } else { 1 };
+    shift(today, sign * amount, unit)
+}
+
+fn english_weekday(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let captures = ENGLISH_WEEKDAY.captures(value)?;
+    let weekday = match &captures[2] {
+        "monday" | "mon" => Weekday::Mon,
+        "tuesday" | "tue" | "tues" => Weekday::Tue,
+        "wednesday" | "wed" => Weekday::Wed,
+        "thursday" | "thu" | "thur" | "thurs" => Weekday::Thu,
+        "friday" | "fri" => Weekday::Fri,
+        "saturday" | "sat" => Weekday::Sat,
+        "sunday" | "sun" => Weekday::Sun,
+        _ => return None,
+    };
+
+    let days_back = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    match captures.get(1).map(|modifier| modifier.as_str()) {
+        Some("last") => today.checked_sub_days(Days::new(if days_back == 0 { 7 } else { days_back.into() })),
+        Some("next") => {
+            let days_ahead = (7 - days_back) % 7;
+            today.checked_add_days(Days::new(if days_ahead == 0 { 7 } else { days_ahead.into() }))
+        }
+        _ => week_day(today, weekday, 0),
+    }
+}
+
+fn chinese_weekday(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let captures = CHINESE_WEEKDAY.captures(value)?;
+    let weeks = match captures.get(1).map(|modifier| modifier.as_str()) {
+        Some("上上") => -2,
+        Some("上") => -1,
+        Some("下") => 1,
+        Some("下下") => 2,
+        _ => 0,
+    };
+    let weekday = match &captures[3] {
+        "一" => Weekday::Mon,
+        "二" => Weekday::Tue,
+        "三" => Weekday::Wed,
+        "四" => Weekday::Thu,
+        "五" => Weekday::Fri,
+        "六" => Weekday::Sat,
+        _ => Weekday::Sun,
+    };
+    week_day(today, weekday, weeks)
+}
+
+/// 以 `today` 所在的周为基准，往前或往后 `weeks` 周中的星期 `weekday`
+fn week_day(today: NaiveDate, weekday: Weekday, weeks: i64) -> Option<NaiveDate> {
+    let offset = weeks * 7 + i64::from(weekday.num_days_from_monday()) - i64::from(today.weekday().num_days_from_monday());
+    shift(today, offset, 'd')
+}
+
+/// 按天（`d`）、周（`w`）或月（`m`）移动日期；月末日期按目标月的最后一天处理
+fn shift(date: NaiveDate, amount: i64, unit: char) -> Option<NaiveDate> {
+    let magnitude = amount.unsigned_abs();
+    match unit {
+        'm' => {
+            let months = Months::new(magnitude.try_into().ok()?);
+            if amount < 0 {
+                date.checked_sub_months(months)
+            } else {
+                date.checked_add_months(months)
+            }
+        }
+        _ => {
+            let days = Days::new(if unit == 'w' { magnitude.checked_mul(7)? } else { magnitude });
+            if amount < 0 {
+                date.checked_sub_days(days)
+            } else {
+                date.checked_add_days(days)
+            }
+        }
+    }
+}
+
+/// 阿拉伯数字或一到九十九的中文数字
+fn chinese_number(value: &str) -> Option<i64> {
+    if let Ok(number) = value.parse() {
+        return Some(number);
+    }
+    let digit = |c: char| "零一二三四五六七八九".chars().position(|d| d == c).or((c == '两').then_some(2));
+    let chars: Vec<char> = value.chars().collect();
+    let number = match chars.as_slice() {
+        ['十'] => 10,
+        [c] => digit(*c)?,
+        ['十', ones] => 10 + digit(*ones)?,
+        [tens, '十'] => digit(*tens)? * 10,
+        [tens, '十', ones] => digit(*tens)? * 10 + digit(*ones)?,
+        _ => return None,
+    };
+    Some(number as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        // 2024-03-13 是周三
+        let today = date(3, 13);
+        let cases = [
+            ("2024-03-01", date(3, 1)),
+            ("2024/3/1", date(3, 1)),
+            ("20240229", date(2, 29)),
+            ("2024年3月1日", date(3, 1)),
+            ("3月1号", date(3, 1)),
+            ("03-01", date(3, 1)),
+            ("Today", today),
+            ("yesterday", date(3, 12)),
+            ("昨天", date(3, 12)),
+            ("明天", date(3, 14)),
+            ("前天", date(3, 11)),
+            ("day after tomorrow", date(3, 15)),
+            ("-3d", date(3, 10)),
+            ("+2", date(3, 15)),
+            ("-1w", date(3, 6)),
+            ("-1m", date(2, 13)),
+            ("3 days ago", date(3, 10)),
+            ("in 2 weeks", date(3, 27)),
+            ("a week ago", date(3, 6)),
+            ("3天前", date(3, 10)),
+            ("两周后", date(3, 27)),
+            ("十天以前", date(3, 3)),
+            ("1个月后", date(4, 13)),
+            ("last friday", date(3, 8)),
+            ("last  Wednesday", date(3, 6)),
+            ("next friday", date(3, 15)),
+            ("next wed", date(3, 20)),
+            ("friday", date(3, 15)),
+            ("this monday", date(3, 11)),
+            ("上周五", date(3, 8)),
+            ("上星期一", date(3, 4)),
+            ("下周日", date(3, 24)),
+            ("本周三", today),
+            ("周日", date(3, 17)),
+            ("上上礼拜五", date(3, 1)),
+        ];
+        for (expression, expected) in cases {
+            assert_eq!(parse(expression, today), Some(expected), "{}", expression);
+        }
+
+        // 周六时 last friday 是昨天，上周五则是上一个自然周的周五
+        let saturday = date(3, 16);
+        assert_eq!(parse("last friday", saturday), Some(date(3, 15)));
+        assert_eq!(parse("上周五", saturday), Some(date(3, 8)));
+
+        // 月末按目标月的最后一天处理
+        assert_eq!(parse("-1m", date(3, 31)), Some(date(2, 29)));
+
+        for invalid in ["", "someday", "2024-02-30", "13月1日", "last", "3 days", "in 3 days ago", "周八", "-3y"] {
+            assert_eq!(parse(invalid, today), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_chinese_number() {
+        assert_eq!(chinese_number("3"), Some(3));
+        assert_eq!(chinese_number("两"), Some(2));
+        assert_eq!(chinese_number("十"), Some(10));
+        assert_eq!(chinese_number("十二"), Some(12));
+        assert_eq!(chinese_number("二十"), Some(20));
+        assert_eq!(chinese_number("三十五"), Some(35));
+        assert_eq!(chinese_number("百"), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! `smartfolderzen://` 链接，如 `smartfolderzen://open?date=yesterday&profile=work`
+
+use crate::actions::{self, Action};
+use crate::error::{AppError, AppResult};
+use chrono::{Local, NaiveDate};
+use tauri::{Emitter, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+pub const SCHEME: &str = "smartfolderzen";
+
+/// 链接无效或执行失败，负载为 `AppError`
+pub const DEEP_LINK_FAILED_EVENT: &str = "deep-link-failed";
+
+/// 注册链接处理：运行中收到的链接，以及通过链接启动时携带的链接
+pub fn init(app: &tauri::AppHandle) {
+    // 安装包会注册协议；开发版或未经安装的程序在这里补注册
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("Failed to register URL scheme: {}", e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, url.as_str());
+        }
+    });
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            handle_url(app, url.as_str());
+        }
+    }
+}
+
+/// 启动参数是否为链接；此时由链接处理负责，不再显示主窗口
+pub fn is_deep_link(argv: &[String]) -> bool {
+    argv.iter()
+        .skip(1)
+        .any(|arg| arg.starts_with(&format!("{}:", SCHEME)))
+}
+
+fn handle_url(app: &tauri::AppHandle, url: &str) {
+    let today = Local::now().date_naive();
+    match parse(url, today) {
+        // 任何网页都能发出链接，而创建会执行配置的钩子命令，必须由用户确认
+        Ok(action @ Action::CreateFolder { .. }) => confirm_create(app, url, action),
+        result => report(app, url, result.and_then(|action| action.perform(app))),
+    }
+}
+
+fn confirm_create(app: &tauri::AppHandle, url: &str, action: Action) {
+    let Action::CreateFolder { profile_id, date } = &action else {
+        return;
+    };
+    let profile = profile_id.as_deref().map(|id| format!("（配置 {}）", id)).unwrap_or_default();
+    let message = format!(
+        "链接请求创建 {} 的文件夹{}，并执行该配置的创建后钩子。\n\n{}",
+        date, profile, url
+    );
+
+    let handle = app.clone();
+    let url = url.to_string();
+    app.dialog()
+        .message(message)
+        .title("确认创建文件夹")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("创建".to_string(), "取消".to_string()))
+        .show(move |confirmed| {
+            if confirmed {
+                report(&handle, &url, action.perform(&handle));
+            } else {
+                println!("Link {} was not confirmed", url);
+            }
+        });
+}
+
+fn report(app: &tauri::AppHandle, url: &str, result: AppResult<String>) {
+    if let Err(e) = result {
+        eprintln!("Failed to handle link {}: {}", url, e);
+        let _ = app.emit(DEEP_LINK_FAILED_EVENT, e);
+    }
+}
+
+/// 将链接解析为操作
+///
+/// 支持 `open` 和 `create`（执行前需用户确认）两种操作，参数 `date`（默认今天，格式见 [`actions::parse_date`]）
+/// 和 `profile`（默认第一个配置）。
+pub fn parse(url: &str, today: NaiveDate) -> AppResult<Action> {
+    let url = Url::parse(url)
+        .map_err(|e| AppError::configuration(&format!("链接格式无效: {}", e)))?;
+    if url.scheme() != SCHEME {
+        return Err(AppError::configuration(&format!("不支持的链接协议: {}", url.scheme())));
+    }
+
+    let mut profile_id = None;
+    let mut date = today;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "profile" if !value.is_empty() => profile_id = Some(value.into_owned()),
+            "date" => date = actions::parse_date(&value, today)?,
+            _ => return Err(AppError::configuration(&format!("无效的链接参数: {}={}", key, value))),
+        }
+    }
+
+    // `smartfolderzen://open` 的操作名在主机位置，`smartfolderzen:open` 在路径位置
+    let name = url.host_str().unwrap_or(url.path()).trim_matches('/');
+    match name {
+        "open" => Ok(Action::OpenFolder { profile_id, date }),
+        "create" => Ok(Action::CreateFolder { profile_id, date }),
+        _ => Err(AppError::configuration(&format!("未知的链接操作: {}", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let today = date(3, 15);
+
+        assert_eq!(
+            parse("smartfolderzen://open?date=yesterday&profile=work", today).unwrap(),
+            Action::OpenFolder { profile_id: Some("work".to_string()), date: date(3, 14) }
+        );
+        assert_eq!(
+            parse("smartfolderzen://create/?date=2024-03-01", today).unwrap(),
+            Action::CreateFolder { profile_id: None, date: date(3, 1) }
+        );
+        assert_eq!(
+            parse("smartfolderzen:open", today).unwrap(),
+            Action::OpenFolder { profile_id: None, date: today }
+        );
+
+        for invalid in [
+            "not a url",
+            "https://open?date=today",
+            "smartfolderzen://delete",
+            "smartfolderzen://open?date=someday",
+            "smartfolderzen://open?folder=x",
+        ] {
+            let error = parse(invalid, today).unwrap_err();
+            assert!(matches!(error.error_type, crate::error::ErrorType::Configuration), "{}", invalid);
+        }
+
+        assert!(is_deep_link(&["app".to_string(), "smartfolderzen://open".to_string()]));
+        assert!(!is_deep_link(&["app".to_string(), "--open-today".to_string()]));
+    }
+}
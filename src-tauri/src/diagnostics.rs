@@ -0,0 +1,12 @@
+use crate::error::AppError;
+use crate::settings::ConfigRecovery;
+use serde::Serialize;
+
+/// 启动过程中发生、需要告知用户的问题，由 `get_startup_diagnostics` 命令提供给前端
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StartupDiagnostics {
+    /// 配置文件损坏时的恢复记录
+    pub config_recovery: Option<ConfigRecovery>,
+    /// 无法加载配置、临时使用默认设置时的错误
+    pub config_error: Option<AppError>,
+}
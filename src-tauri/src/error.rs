@@ -1,13 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppError {
     pub message: String,
     pub error_type: ErrorType,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ErrorType {
     FileSystem,
     InvalidPath,
@@ -16,12 +16,27 @@ pub enum ErrorType {
     Unknown,
 }
 
+impl ErrorType {
+    /// 命令行模式下的进程退出码；0 表示成功，2 保留给参数错误
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorType::Unknown => 1,
+            ErrorType::FileSystem => 3,
+            ErrorType::InvalidPath => 4,
+            ErrorType::PermissionDenied => 5,
+            ErrorType::Configuration => 6,
+        }
+    }
+}
+
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}: {}", self.error_type_str(), self.message)
     }
 }
 
+impl std::error::Error for AppError {}
+
 impl AppError {
     pub fn new(message: &str, error_type: ErrorType) -> Self {
         Self {
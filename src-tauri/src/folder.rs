@@ -1,58 +1,244 @@
 use crate::error::{AppError, AppResult};
-use crate::settings::AppSettings;
-use chrono::{Local, NaiveDate};
+use crate::hooks;
+use crate::journal::{self, Entry, Journal, Operation, OperationKind};
+use crate::scaffold;
+use crate::settings::{infer_year, Profile};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct FolderManager;
 
+/// 一个日期文件夹及其是否已存在
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderInfo {
+    pub profile_id: String,
+    pub date: NaiveDate,
+    pub path: String,
+    pub exists: bool,
+}
+
+/// 要打开的日期还没有文件夹时怎么办
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingFolderPolicy {
+    /// 打开日期最接近的已有日期文件夹，一个都没有时打开基础路径
+    #[default]
+    Nearest,
+    /// 创建当天的文件夹再打开
+    Create,
+    /// 报错，不打开任何文件夹
+    Error,
+}
+
+/// 扫描结果中日期的可靠程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderKind {
+    /// 名称能唯一确定日期
+    Day,
+    /// 名称中没有年份，年份由修改时间或参考日期推断
+    InferredYear,
+}
+
+/// [`FolderManager::scan`] 找到的一个日期文件夹
+#[derive(Debug, Clone, Serialize)]
+pub struct CatalogEntry {
+    pub date: NaiveDate,
+    pub path: PathBuf,
+    pub kind: FolderKind,
+}
+
 impl FolderManager {
     /// 创建今天的文件夹
-    pub fn create_today_folder(settings: &AppSettings) -> AppResult<String> {
+    pub fn create_today_folder(profile: &Profile, journal: &Journal) -> AppResult<String> {
         let today = Local::now().date_naive();
-        Self::create_folder_for_date(settings, &today)
+        Self::create_folder_for_date(profile, &today, journal)
     }
 
-    /// 为指定日期创建文件夹
-    pub fn create_folder_for_date(settings: &AppSettings, date: &NaiveDate) -> AppResult<String> {
+    /// 为指定日期创建文件夹，新建的目录和脚手架文件记入 `journal`
+    pub fn create_folder_for_date(profile: &Profile, date: &NaiveDate, journal: &Journal) -> AppResult<String> {
         // 验证基础路径
-        settings.validate_path()?;
+        profile.validate_path()?;
+
+        let folder_path = Self::folder_path_for_date(profile, date)?;
+
+        // 文件夹不存在时才创建；新建的各级目录记入操作日志，最上层的在钩子要求回滚时一并删除
+        let mut entries = Vec::new();
+        journal::create_dirs(&folder_path, &mut entries)?;
+        let created_root = match entries.first() {
+            Some(Entry::Create { path, .. }) => {
+                eprintln!("Created folder: {}", folder_path.display());
+                Some(path.clone())
+            }
+            _ => None,
+        };
 
-        let folder_name = settings.date_format.format_date(date);
-        let folder_path = PathBuf::from(&settings.folder_path).join(&folder_name);
+        // 补齐脚手架，已存在的文件不会被覆盖
+        let scaffolded = scaffold::apply(&folder_path, &profile.scaffold, date)?;
 
-        // 检查文件夹是否已存在
-        if folder_path.exists() {
-            return Ok(folder_path.to_string_lossy().to_string());
+        // 只对新建的文件夹执行钩子；可能要求回滚的钩子执行完之前不会报告创建成功
+        if let Some(root) = created_root {
+            if let Err(e) = hooks::run_all(profile, &folder_path, date) {
+                let _ = fs::remove_dir_all(&root);
+                eprintln!("Rolled back folder {}: {}", root.display(), e);
+                return Err(e);
+            }
         }
 
-        // 创建文件夹
-        fs::create_dir_all(&folder_path)
-            .map_err(|e| AppError::file_system(&format!("创建文件夹失败: {}", e)))?;
+        entries.extend(scaffolded.iter().map(|path| Entry::created(path)));
+        journal.record(&Operation {
+            entries,
+            ..Operation::new(OperationKind::CreateFolder {
+                profile_id: profile.id.clone(),
+                date: *date,
+            })
+        });
 
-        println!("Created folder: {}", folder_path.display());
         Ok(folder_path.to_string_lossy().to_string())
     }
 
     /// 检查今天的文件夹是否存在
-    pub fn today_folder_exists(settings: &AppSettings) -> bool {
+    pub fn today_folder_exists(profile: &Profile) -> bool {
         let today = Local::now().date_naive();
-        Self::folder_exists_for_date(settings, &today)
+        Self::folder_exists_for_date(profile, &today)
     }
 
     /// 检查指定日期的文件夹是否存在
-    pub fn folder_exists_for_date(settings: &AppSettings, date: &NaiveDate) -> bool {
-        let folder_name = settings.date_format.format_date(date);
-        let folder_path = PathBuf::from(&settings.folder_path).join(&folder_name);
-        folder_path.exists() && folder_path.is_dir()
+    pub fn folder_exists_for_date(profile: &Profile, date: &NaiveDate) -> bool {
+        match Self::folder_path_for_date(profile, date) {
+            Ok(folder_path) => folder_path.is_dir(),
+            Err(_) => false,
+        }
     }
 
     /// 获取今天应该创建的文件夹路径
-    pub fn get_today_folder_path(settings: &AppSettings) -> String {
+    pub fn get_today_folder_path(profile: &Profile) -> AppResult<String> {
         let today = Local::now().date_naive();
-        let folder_name = settings.date_format.format_date(&today);
-        let folder_path = PathBuf::from(&settings.folder_path).join(&folder_name);
-        folder_path.to_string_lossy().to_string()
+        let folder_path = Self::folder_path_for_date(profile, &today)?;
+        Ok(folder_path.to_string_lossy().to_string())
+    }
+
+    /// 获取指定日期的文件夹路径（含分层目录）
+    pub fn folder_path_for_date(profile: &Profile, date: &NaiveDate) -> AppResult<PathBuf> {
+        Ok(PathBuf::from(&profile.folder_path).join(profile.relative_folder_path(date)?))
+    }
+
+    /// 获取指定日期的文件夹路径及其是否已存在
+    pub fn folder_info(profile: &Profile, date: &NaiveDate) -> AppResult<FolderInfo> {
+        let path = Self::folder_path_for_date(profile, date)?;
+        Ok(FolderInfo {
+            profile_id: profile.id.clone(),
+            date: *date,
+            exists: path.is_dir(),
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    /// 列出某个月中已存在的日期文件夹
+    pub fn list_month(profile: &Profile, year: i32, month: u32) -> AppResult<Vec<FolderInfo>> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)
+            .ok_or_else(|| AppError::configuration("月份无效"))?;
+
+        let mut folders = Vec::new();
+        for date in first.iter_days().take_while(|date| date.month() == month) {
+            let folder = Self::folder_info(profile, &date)?;
+            if folder.exists {
+                folders.push(folder);
+            }
+        }
+        Ok(folders)
+    }
+
+    /// 扫描基础路径，找出按本配置命名的全部日期文件夹，按日期排序
+    ///
+    /// 只查看与命名层级同样深度的目录，名称解析不出日期的目录被忽略。名称中没有年份（如 MMDD）时，
+    /// 取不晚于 `reference` 的最近一年；`reference` 为空时以文件夹的修改时间为参考。
+    pub fn scan(profile: &Profile, reference: Option<NaiveDate>) -> AppResult<Vec<CatalogEntry>> {
+        let base = PathBuf::from(&profile.folder_path);
+        let mut entries: Vec<CatalogEntry> = Self::dirs_at_depth(&base, profile.naming_levels().len())
+            .into_iter()
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&base).ok()?;
+                let mut kind = FolderKind::Day;
+                let date = profile.date_from_relative_path_with(relative, |month, day| {
+                    kind = FolderKind::InferredYear;
+                    let reference = match reference {
+                        Some(reference) => reference,
+                        None => {
+                            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+                            DateTime::<Local>::from(modified).date_naive()
+                        }
+                    };
+                    infer_year(month, day, reference)
+                })?;
+                Some(CatalogEntry { date, path, kind })
+            })
+            .collect();
+        entries.sort_by(|a, b| (a.date, &a.path).cmp(&(b.date, &b.path)));
+        Ok(entries)
+    }
+
+    /// `base` 下恰好位于第 `depth` 层的全部目录
+    pub fn dirs_at_depth(base: &Path, depth: usize) -> Vec<PathBuf> {
+        let mut level = vec![base.to_path_buf()];
+        for _ in 0..depth {
+            level = level
+                .iter()
+                .filter_map(|dir| fs::read_dir(dir).ok())
+                .flatten()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+                .map(|entry| entry.path())
+                .collect();
+        }
+        level
+    }
+
+    /// 要打开的指定日期的文件夹；文件夹不存在时按 `policy` 处理
+    pub fn resolve_open_path(
+        profile: &Profile,
+        date: &NaiveDate,
+        policy: MissingFolderPolicy,
+        journal: &Journal,
+    ) -> AppResult<String> {
+        let path = Self::folder_path_for_date(profile, date)?;
+        if path.is_dir() {
+            return Ok(path.to_string_lossy().to_string());
+        }
+
+        match policy {
+            MissingFolderPolicy::Create => Self::create_folder_for_date(profile, date, journal),
+            MissingFolderPolicy::Error => Err(AppError::invalid_path(&format!("{} 的文件夹不存在", date))),
+            MissingFolderPolicy::Nearest => {
+                // 相差天数相同时取较早的一天
+                let nearest = Self::scan(profile, None)?
+                    .into_iter()
+                    .min_by_key(|entry| ((entry.date - *date).num_days().abs(), entry.date));
+                Ok(match nearest {
+                    Some(entry) => entry.path.to_string_lossy().to_string(),
+                    None => Self::get_open_path_for_date(profile, date),
+                })
+            }
+        }
+    }
+
+    /// 还没有任何日期文件夹时的后备：已存在的上级目录（如当月），或基础路径
+    fn get_open_path_for_date(profile: &Profile, date: &NaiveDate) -> String {
+        let base = PathBuf::from(&profile.folder_path);
+
+        let nearest = Self::folder_path_for_date(profile, date)
+            .ok()
+            .and_then(|path| {
+                path.ancestors()
+                    .take_while(|ancestor| ancestor.starts_with(&base) && *ancestor != base)
+                    .find(|ancestor| ancestor.is_dir())
+                    .map(|ancestor| ancestor.to_path_buf())
+            })
+            .unwrap_or(base);
+
+        nearest.to_string_lossy().to_string()
     }
 
     /// 打开文件夹在文件管理器中
@@ -66,7 +252,7 @@ impl FolderManager {
         #[cfg(target_os = "windows")]
         {
             std::process::Command::new("explorer")
-                .arg(&path)
+                .arg(path)
                 .spawn()
                 .map_err(|_| AppError::file_system("无法打开文件管理器"))?;
         }
@@ -74,7 +260,7 @@ impl FolderManager {
         #[cfg(target_os = "macos")]
         {
             std::process::Command::new("open")
-                .arg(&path)
+                .arg(path)
                 .spawn()
                 .map_err(|_| AppError::file_system("无法打开Finder"))?;
         }
@@ -82,7 +268,7 @@ impl FolderManager {
         #[cfg(target_os = "linux")]
         {
             std::process::Command::new("xdg-open")
-                .arg(&path)
+                .arg(path)
                 .spawn()
                 .map_err(|_| AppError::file_system("无法打开文件管理器"))?;
         }
@@ -123,27 +309,195 @@ mod tests {
     #[test]
     fn test_create_folder() {
         let temp_dir = tempdir().unwrap();
-        let settings = AppSettings {
+        let profile = Profile {
+            id: "default".to_string(),
+            name: "默认".to_string(),
             folder_path: temp_dir.path().to_string_lossy().to_string(),
             date_format: DateFormat::YYYYMMDD,
-            auto_start: true,
-            auto_create_on_startup: true,
+            folder_levels: Vec::new(),
+            scaffold: Vec::new(),
+            auto_create: true,
+            hooks: Vec::new(),
         };
 
-        let result = FolderManager::create_today_folder(&settings);
+        let journal = Journal::at(temp_dir.path().join(journal::JOURNAL_FILE));
+        let result = FolderManager::create_today_folder(&profile, &journal);
         assert!(result.is_ok());
         
         let folder_path = result.unwrap();
         assert!(PathBuf::from(&folder_path).exists());
     }
 
+    #[test]
+    fn test_create_nested_folder() {
+        let temp_dir = tempdir().unwrap();
+        let profile = Profile {
+            folder_path: temp_dir.path().to_string_lossy().to_string(),
+            folder_levels: vec![
+                DateFormat::Custom("%Y".to_string()),
+                DateFormat::Custom("%Y-%m %B".to_string()),
+                DateFormat::Custom("%d {weekday}".to_string()),
+            ],
+            ..Profile::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        assert!(!FolderManager::folder_exists_for_date(&profile, &date));
+        let journal = Journal::at(temp_dir.path().join(journal::JOURNAL_FILE));
+        let folder_path = FolderManager::create_folder_for_date(&profile, &date, &journal).unwrap();
+
+        assert_eq!(
+            PathBuf::from(&folder_path),
+            temp_dir.path().join("2024").join("2024-03 March").join("15 Fri")
+        );
+        assert!(FolderManager::folder_exists_for_date(&profile, &date));
+        assert!(profile.validate().is_ok());
+
+        // 新建的三级目录记入操作日志，撤销后全部删除
+        let root = temp_dir.path().join("2024");
+        let operations = journal.operations().unwrap();
+        assert_eq!(operations.len(), 1);
+        let operation = &operations[0];
+        assert!(matches!(operation.entries.first(), Some(Entry::Create { path, .. }) if *path == root));
+        assert_eq!(operation.entries.len(), 3);
+        journal.undo(&operation.id).unwrap();
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_day_folders() {
+        let temp_dir = tempdir().unwrap();
+        let profile = Profile {
+            folder_path: temp_dir.path().to_string_lossy().to_string(),
+            folder_levels: vec![
+                DateFormat::Custom("%Y".to_string()),
+                DateFormat::Custom("%m-%d {weekday}".to_string()),
+            ],
+            ..Profile::default()
+        };
+        for name in ["2024/03-15 Fri", "2024/03-16 Sat", "2023/12-31 Sun", "2024/03-17 Mon", "2024/notes", "misc/03-15 Fri"] {
+            fs::create_dir_all(temp_dir.path().join(name)).unwrap();
+        }
+        fs::write(temp_dir.path().join("2024").join("03-18 Mon"), "").unwrap();
+
+        let entries = FolderManager::scan(&profile, None).unwrap();
+        let dates: Vec<String> = entries.iter().map(|entry| entry.date.to_string()).collect();
+        assert_eq!(dates, ["2023-12-31", "2024-03-15", "2024-03-16"]);
+        assert!(entries.iter().all(|entry| entry.kind == FolderKind::Day));
+    }
+
+    #[test]
+    fn test_resolve_open_path() {
+        let temp_dir = tempdir().unwrap();
+        let profile = Profile {
+            folder_path: temp_dir.path().to_string_lossy().to_string(),
+            date_format: DateFormat::YYYYMMDD,
+            ..Profile::default()
+        };
+        let date = |day: u32| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        let journal = Journal::at(temp_dir.path().join("data").join(journal::JOURNAL_FILE));
+        let resolve = |day: u32, policy| FolderManager::resolve_open_path(&profile, &date(day), policy, &journal);
+        let base = temp_dir.path().to_string_lossy().to_string();
+
+        // 还没有任何日期文件夹时打开基础路径
+        assert_eq!(resolve(15, MissingFolderPolicy::Nearest).unwrap(), base);
+        for name in ["2024-03-10", "2024-03-20"] {
+            fs::create_dir(temp_dir.path().join(name)).unwrap();
+        }
+
+        let path = |name: &str| temp_dir.path().join(name).to_string_lossy().to_string();
+        assert_eq!(resolve(10, MissingFolderPolicy::Error).unwrap(), path("2024-03-10"));
+        assert_eq!(resolve(16, MissingFolderPolicy::Nearest).unwrap(), path("2024-03-20"));
+        assert_eq!(resolve(15, MissingFolderPolicy::Nearest).unwrap(), path("2024-03-10"));
+        assert!(resolve(15, MissingFolderPolicy::Error).is_err());
+        assert_eq!(resolve(15, MissingFolderPolicy::Create).unwrap(), path("2024-03-15"));
+        assert!(temp_dir.path().join("2024-03-15").is_dir());
+    }
+
+    #[test]
+    fn test_scan() {
+        let temp_dir = tempdir().unwrap();
+        let profile = Profile {
+            folder_path: temp_dir.path().to_string_lossy().to_string(),
+            date_format: DateFormat::MMDD,
+            ..Profile::default()
+        };
+        for name in ["0315", "1231", "0230", "misc"] {
+            fs::create_dir(temp_dir.path().join(name)).unwrap();
+        }
+
+        let reference = NaiveDate::from_ymd_opt(2025, 1, 2);
+        let entries = FolderManager::scan(&profile, reference).unwrap();
+        let dates: Vec<String> = entries.iter().map(|entry| entry.date.to_string()).collect();
+        assert_eq!(dates, ["2024-03-15", "2024-12-31"]);
+        assert!(entries.iter().all(|entry| entry.kind == FolderKind::InferredYear));
+        assert_eq!(entries[0].path, temp_dir.path().join("0315"));
+
+        // 没有参考日期时按修改时间（刚刚创建）推断
+        let today = Local::now().date_naive();
+        let entries = FolderManager::scan(&profile, None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.date <= today && entry.date > today - chrono::Days::new(366)));
+    }
+
+    #[test]
+    fn test_levels_must_vary_by_date() {
+        let profile = Profile {
+            folder_levels: vec![DateFormat::Custom("%Y".to_string())],
+            ..Profile::default()
+        };
+        assert!(profile.validate().is_err());
+    }
+
     #[test]
     fn test_date_format() {
         use chrono::NaiveDate;
         
         let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
         
-        assert_eq!(DateFormat::MMDD.format_date(&date), "0315");
-        assert_eq!(DateFormat::YYYYMMDD.format_date(&date), "2024-03-15");
+        assert_eq!(DateFormat::MMDD.format_date(&date).unwrap(), "0315");
+        assert_eq!(DateFormat::YYYYMMDD.format_date(&date).unwrap(), "2024-03-15");
+        assert_eq!(
+            DateFormat::Custom("%Y-%m-%d_{weekday}".to_string()).format_date(&date).unwrap(),
+            "2024-03-15_Fri"
+        );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_date() {
+        // 每种格式都能从生成的名称还原日期；MMDD 取不晚于参考日期的最近一年
+        let parse = |format: &DateFormat, name: &str, reference: NaiveDate| {
+            let profile = Profile {
+                date_format: format.clone(),
+                ..Profile::default()
+            };
+            profile.date_from_relative_path_with(Path::new(name), |month, day| infer_year(month, day, reference))
+        };
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let formats = [
+            DateFormat::MMDD,
+            DateFormat::YYYYMMDD,
+            DateFormat::Custom("%Y-%m-%d_{weekday}".to_string()),
+            DateFormat::Custom("{year}年{month}月{day}日 第{iso_week}周".to_string()),
+            DateFormat::Custom("%d.%m.%Y Q{quarter}".to_string()),
+        ];
+        for format in &formats {
+            let name = format.format_date(&date).unwrap();
+            assert_eq!(parse(format, &name, date), Some(date), "{}", name);
+        }
+
+        let reference = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        assert_eq!(parse(&DateFormat::MMDD, "1231", reference), NaiveDate::from_ymd_opt(2024, 12, 31));
+        assert_eq!(parse(&DateFormat::MMDD, "0229", reference), NaiveDate::from_ymd_opt(2024, 2, 29));
+        assert_eq!(parse(&DateFormat::MMDD, "0230", reference), None);
+        assert_eq!(parse(&DateFormat::YYYYMMDD, "2024-3-15", reference), None);
+        assert_eq!(parse(&DateFormat::YYYYMMDD, "notes", reference), None);
+        assert_eq!(parse(&formats[2], "2024-03-15_Mon", reference), None);
+        let ymd = |year: i32, month: u32, day: u32| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        assert_eq!(infer_year(3, 15, ymd(2024, 3, 15)), Some(2024));
+        assert_eq!(infer_year(12, 31, ymd(2025, 1, 2)), Some(2024));
+        assert_eq!(infer_year(2, 29, ymd(2023, 6, 1)), Some(2020));
+        assert_eq!(infer_year(2, 30, ymd(2023, 6, 1)), None);
+    }
+}
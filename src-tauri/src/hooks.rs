@@ -0,0 +1,415 @@
+use crate::error::{AppError, AppResult};
+use crate::settings::Profile;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// 内存中保留的最近执行记录数量
+const LOG_CAPACITY: usize = 50;
+/// 每个输出流最多保留的字节数
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+/// 命令结束后等待输出读取完毕的最长时间（命令启动的后台进程可能一直占着管道）
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+static HOOK_LOG: Mutex<VecDeque<HookRun>> = Mutex::new(VecDeque::new());
+/// 还在执行钩子的后台线程，见 [`wait_all`]
+static WORKERS: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// 新建日期文件夹后执行的命令，如 `git init`
+///
+/// 命令通过系统 shell 执行（Windows 为 `cmd /C`，其他平台为 `sh -c`），
+/// 并通过环境变量获得新文件夹的信息：
+/// `SFZ_FOLDER_PATH`、`SFZ_DATE`（YYYY-MM-DD）、`SFZ_PROFILE_ID`、`SFZ_PROFILE_NAME`。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hook {
+    /// 显示在日志中的名称，为空时显示命令本身
+    pub name: String,
+    pub command: String,
+    /// 工作目录，为空时为新建的文件夹
+    pub working_dir: Option<String>,
+    /// 超时秒数，超时后结束进程并按失败处理
+    pub timeout_secs: u64,
+    pub on_failure: FailurePolicy,
+}
+
+/// 命令失败（非零退出、超时或无法启动）时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// 只记录日志
+    Ignore,
+    /// 记录日志并输出警告，文件夹保留
+    #[default]
+    Warn,
+    /// 删除刚创建的文件夹，创建操作整体失败；后续命令不再执行
+    Rollback,
+}
+
+impl Default for Hook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            working_dir: None,
+            timeout_secs: 30,
+            on_failure: FailurePolicy::default(),
+        }
+    }
+}
+
+impl Hook {
+    fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.command
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// 一次命令执行的记录，供界面查看
+#[derive(Debug, Clone, Serialize)]
+pub struct HookRun {
+    pub hook: String,
+    pub profile_id: String,
+    pub folder_path: String,
+    pub date: NaiveDate,
+    pub started_at: DateTime<Local>,
+    pub duration_ms: u64,
+    pub success: bool,
+    /// 进程退出码；超时被结束或无法启动时为空
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub on_failure: FailurePolicy,
+}
+
+pub fn validate(hooks: &[Hook]) -> AppResult<()> {
+    for hook in hooks {
+        if hook.command.trim().is_empty() {
+            return Err(AppError::configuration("钩子命令不能为空"));
+        }
+        if hook.timeout_secs == 0 {
+            return Err(AppError::configuration(&format!(
+                "钩子 {} 的超时时间必须大于 0",
+                hook.display_name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 依次执行配置的钩子，结果写入执行记录
+///
+/// 直到最后一个策略为 [`FailurePolicy::Rollback`] 的钩子为止同步执行，其中有钩子要求回滚时返回错误，
+/// 由调用方删除文件夹；之后只会忽略或警告的钩子在后台线程中执行，不阻塞调用方（如托盘菜单）。
+pub fn run_all(profile: &Profile, folder: &Path, date: &NaiveDate) -> AppResult<()> {
+    let blocking = profile
+        .hooks
+        .iter()
+        .rposition(|hook| hook.on_failure == FailurePolicy::Rollback)
+        .map_or(0, |index| index + 1);
+    run_hooks(&profile.hooks[..blocking], profile, folder, date)?;
+
+    if blocking < profile.hooks.len() {
+        let (profile, folder, date) = (profile.clone(), folder.to_path_buf(), *date);
+        let worker = std::thread::spawn(move || {
+            let _ = run_hooks(&profile.hooks[blocking..], &profile, &folder, &date);
+        });
+        if let Ok(mut workers) = WORKERS.lock() {
+            workers.retain(|worker| !worker.is_finished());
+            workers.push(worker);
+        }
+    }
+    Ok(())
+}
+
+fn run_hooks(hooks: &[Hook], profile: &Profile, folder: &Path, date: &NaiveDate) -> AppResult<()> {
+    for hook in hooks {
+        let run = run(hook, profile, folder, date);
+        let success = run.success;
+        let detail = failure_detail(&run);
+        record(run);
+
+        if success {
+            continue;
+        }
+        match hook.on_failure {
+            FailurePolicy::Ignore => {}
+            FailurePolicy::Warn => {
+                eprintln!("Warning: hook {} failed: {}", hook.display_name(), detail);
+            }
+            FailurePolicy::Rollback => {
+                return Err(AppError::file_system(&format!(
+                    "钩子 {} 执行失败: {}",
+                    hook.display_name(),
+                    detail
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 等待所有后台钩子执行完毕；命令行在退出前调用，避免钩子随进程一起被结束
+pub fn wait_all() {
+    let workers = WORKERS
+        .lock()
+        .map(|mut workers| std::mem::take(&mut *workers))
+        .unwrap_or_default();
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+/// 最近的执行记录，最新的在前
+pub fn recent_runs() -> Vec<HookRun> {
+    HOOK_LOG
+        .lock()
+        .map(|log| log.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn record(run: HookRun) {
+    if let Ok(mut log) = HOOK_LOG.lock() {
+        if log.len() == LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(run);
+    }
+}
+
+fn failure_detail(run: &HookRun) -> String {
+    if run.timed_out {
+        "执行超时".to_string()
+    } else if let Some(code) = run.exit_code {
+        format!("退出码 {}", code)
+    } else {
+        run.stderr.clone()
+    }
+}
+
+fn run(hook: &Hook, profile: &Profile, folder: &Path, date: &NaiveDate) -> HookRun {
+    let started_at = Local::now();
+    let started = Instant::now();
+    let mut run = HookRun {
+        hook: hook.display_name().to_string(),
+        profile_id: profile.id.clone(),
+        folder_path: folder.to_string_lossy().to_string(),
+        date: *date,
+        started_at,
+        duration_ms: 0,
+        success: false,
+        exit_code: None,
+        timed_out: false,
+        stdout: String::new(),
+        stderr: String::new(),
+        on_failure: hook.on_failure,
+    };
+
+    let working_dir = hook
+        .working_dir
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| folder.to_path_buf());
+
+    let mut command = shell_command(&hook.command);
+    command
+        .current_dir(&working_dir)
+        .env("SFZ_FOLDER_PATH", folder)
+        .env("SFZ_DATE", date.format("%Y-%m-%d").to_string())
+        .env("SFZ_PROFILE_ID", &profile.id)
+        .env("SFZ_PROFILE_NAME", &profile.name)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            run.stderr = format!("无法启动命令: {}", e);
+            return run;
+        }
+    };
+
+    let stdout = capture(child.stdout.take());
+    let stderr = capture(child.stderr.take());
+
+    let timeout = Duration::from_secs(hook.timeout_secs);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() >= timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                run.timed_out = true;
+                break None;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => {
+                run.stderr = format!("等待命令结束失败: {}", e);
+                break None;
+            }
+        }
+    };
+
+    run.stdout = stdout.recv_timeout(OUTPUT_GRACE).unwrap_or_default();
+    let stderr = stderr.recv_timeout(OUTPUT_GRACE).unwrap_or_default();
+    if !stderr.is_empty() {
+        run.stderr = stderr;
+    }
+    run.exit_code = status.and_then(|status| status.code());
+    run.success = status.is_some_and(|status| status.success());
+    run.duration_ms = started.elapsed().as_millis() as u64;
+    run
+}
+
+#[cfg(windows)]
+fn shell_command(line: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+    /// 不为命令弹出控制台窗口
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut command = Command::new("cmd");
+    command.arg("/C").raw_arg(line).creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(not(windows))]
+fn shell_command(line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(line);
+    command
+}
+
+/// 在后台线程中读取输出流，超出上限的部分被丢弃
+fn capture(stream: Option<impl Read + Send + 'static>) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    if let Some(stream) = stream {
+        std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            let _ = stream.take(MAX_OUTPUT_BYTES as u64).read_to_end(&mut bytes);
+            let _ = tx.send(String::from_utf8_lossy(&bytes).into_owned());
+        });
+    }
+    rx
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::folder::FolderManager;
+    use crate::journal::{Journal, JOURNAL_FILE};
+    use crate::settings::DateFormat;
+
+    fn profile(base: &Path, hooks: Vec<Hook>) -> Profile {
+        Profile {
+            id: "hooks".to_string(),
+            folder_path: base.to_string_lossy().to_string(),
+            date_format: DateFormat::YYYYMMDD,
+            hooks,
+            ..Profile::default()
+        }
+    }
+
+    fn hook(command: &str, on_failure: FailurePolicy) -> Hook {
+        Hook {
+            command: command.to_string(),
+            on_failure,
+            ..Hook::default()
+        }
+    }
+
+    #[test]
+    fn test_hook_receives_environment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let profile = profile(
+            temp_dir.path(),
+            vec![hook(
+                "echo \"$SFZ_DATE $SFZ_PROFILE_ID\" > created.txt && pwd",
+                FailurePolicy::Rollback,
+            )],
+        );
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let journal = Journal::at(temp_dir.path().join(JOURNAL_FILE));
+
+        let path = FolderManager::create_folder_for_date(&profile, &date, &journal).unwrap();
+        wait_all();
+        let written = std::fs::read_to_string(Path::new(&path).join("created.txt")).unwrap();
+        assert_eq!(written.trim(), "2024-03-15 hooks");
+
+        let run = recent_runs()
+            .into_iter()
+            .find(|run| run.folder_path == path)
+            .unwrap();
+        assert!(run.success);
+        assert!(run.stdout.trim().ends_with("2024-03-15"));
+    }
+
+    #[test]
+    fn test_failure_policies() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let journal = Journal::at(temp_dir.path().join(JOURNAL_FILE));
+
+        let warn = profile(temp_dir.path(), vec![hook("exit 3", FailurePolicy::Warn)]);
+        assert!(FolderManager::create_folder_for_date(&warn, &date, &journal).is_ok());
+        assert!(temp_dir.path().join("2024-03-15").is_dir());
+
+        let rollback = profile(
+            temp_dir.path(),
+            vec![
+                hook("echo partial; exit 3", FailurePolicy::Rollback),
+                hook("touch should-not-run", FailurePolicy::Ignore),
+            ],
+        );
+        let next_day = date.succ_opt().unwrap();
+        // 要求回滚的钩子同步执行，失败时创建整体失败，之后的钩子不再执行
+        let error = FolderManager::create_folder_for_date(&rollback, &next_day, &journal).unwrap_err();
+        assert!(error.message.contains("退出码 3"));
+        assert!(!temp_dir.path().join("2024-03-16").exists());
+        wait_all();
+        assert!(!recent_runs().iter().any(|run| run.date == next_day && run.hook == "touch should-not-run"));
+        assert_eq!(journal.operations().unwrap().len(), 1, "only the warn folder is journaled");
+
+        let run = recent_runs()
+            .into_iter()
+            .find(|run| run.date == next_day && run.profile_id == "hooks")
+            .unwrap();
+        assert_eq!(run.exit_code, Some(3));
+        assert_eq!(run.stdout.trim(), "partial");
+    }
+
+    #[test]
+    fn test_timeout() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let slow = Hook {
+            timeout_secs: 1,
+            ..hook("sleep 5", FailurePolicy::Ignore)
+        };
+        let profile = profile(temp_dir.path(), vec![slow]);
+        let date = NaiveDate::from_ymd_opt(2024, 3, 17).unwrap();
+
+        let journal = Journal::at(temp_dir.path().join(JOURNAL_FILE));
+
+        // 创建不等待钩子，钩子超时后被结束
+        let started = Instant::now();
+        FolderManager::create_folder_for_date(&profile, &date, &journal).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        wait_all();
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        let run = recent_runs().into_iter().find(|run| run.date == date).unwrap();
+        assert!(run.timed_out);
+        assert!(!run.success);
+        assert!(validate(&[hook("", FailurePolicy::Warn)]).is_err());
+    }
+}
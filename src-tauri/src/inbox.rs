@@ -0,0 +1,436 @@
+//! 收件箱归档：监视下载目录等来源，把新出现且已写完的文件移入今天的文件夹
+
+use crate::error::{AppError, AppResult};
+use crate::folder::FolderManager;
+use crate::fs_util;
+use crate::journal::{self, Entry, Journal, Operation, OperationKind};
+use crate::rules::{self, FileFacts, RuleOutcome};
+use crate::settings::AppSettings;
+use crate::tray;
+use chrono::Local;
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{Emitter, Manager, Runtime};
+
+/// 检查待归档文件的间隔
+const TICK: Duration = Duration::from_millis(500);
+
+/// 文件已移入今天的文件夹，负载为 [`MovedFile`]
+pub const INBOX_FILE_MOVED_EVENT: &str = "inbox-file-moved";
+
+/// 托盘中的全局暂停开关，只在本次运行中有效
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// 收件箱归档设置，默认关闭
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InboxSettings {
+    pub enabled: bool,
+    /// 被监视的目录，只处理直接位于其中的文件
+    pub sources: Vec<String>,
+    /// 归档到哪个配置的今天文件夹，为空时使用默认配置
+    pub profile_id: Option<String>,
+    /// 文件大小和修改时间保持不变多少秒后才认为已写完
+    pub stable_secs: u64,
+    /// 下载中的临时文件扩展名，这些文件不会被移动
+    pub ignored_extensions: Vec<String>,
+    pub collision: CollisionPolicy,
+}
+
+/// 今天的文件夹里已有同名文件时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    /// 加序号：`report (1).pdf`
+    #[default]
+    Number,
+    /// 加移动时的时间：`report-153012.pdf`
+    Timestamp,
+    /// 不移动，文件留在原处
+    Skip,
+}
+
+impl Default for InboxSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sources: Vec::new(),
+            profile_id: None,
+            stable_secs: 2,
+            ignored_extensions: ["crdownload", "part", "partial", "download", "tmp"]
+                .map(String::from)
+                .to_vec(),
+            collision: CollisionPolicy::default(),
+        }
+    }
+}
+
+impl InboxSettings {
+    /// 是否为下载中的临时文件或隐藏文件
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_none_or(|name| name.starts_with('.'));
+        let temporary = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                self.ignored_extensions
+                    .iter()
+                    .any(|ignored| ignored.trim_start_matches('.').eq_ignore_ascii_case(extension))
+            });
+        hidden || temporary
+    }
+}
+
+/// 一次归档的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct MovedFile {
+    pub from: String,
+    pub to: String,
+}
+
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+pub fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::Relaxed);
+}
+
+/// 文件大小和修改时间，用来判断文件是否还在写入
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Snapshot {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// 跟踪新出现的文件，直到它们在一段时间内不再变化
+#[derive(Default)]
+pub struct PendingFiles {
+    files: HashMap<PathBuf, (Snapshot, Instant)>,
+}
+
+impl PendingFiles {
+    /// 记录一个有变化的文件
+    pub fn observe(&mut self, path: PathBuf, now: Instant) {
+        self.files.entry(path).or_insert((
+            Snapshot { len: u64::MAX, modified: None },
+            now,
+        ));
+    }
+
+    /// 取出已经稳定的文件；消失的文件被丢弃，仍在变化的文件重新计时
+    pub fn take_stable(&mut self, now: Instant, stable_for: Duration) -> Vec<PathBuf> {
+        let mut stable = Vec::new();
+        self.files.retain(|path, (snapshot, since)| {
+            let Ok(metadata) = fs::metadata(path) else {
+                return false;
+            };
+            if !metadata.is_file() {
+                return false;
+            }
+
+            let current = Snapshot {
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+            };
+            if current != *snapshot {
+                *snapshot = current;
+                *since = now;
+                return true;
+            }
+            if now.duration_since(*since) < stable_for {
+                return true;
+            }
+
+            stable.push(path.clone());
+            false
+        });
+        stable
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+}
+
+/// 在目标目录中为文件挑选不冲突的名称；策略为跳过且已存在同名文件时返回 `None`
+pub fn destination(dir: &Path, file_name: &str, policy: CollisionPolicy) -> Option<PathBuf> {
+    destination_with(dir, file_name, policy, &|candidate| candidate.exists())
+}
+
+/// 同 [`destination`]，由 `taken` 判断名称是否已被占用（批量规划时还要避开已分配的名称）
+pub fn destination_with(
+    dir: &Path,
+    file_name: &str,
+    policy: CollisionPolicy,
+    taken: &dyn Fn(&Path) -> bool,
+) -> Option<PathBuf> {
+    let candidate = dir.join(file_name);
+    if !taken(&candidate) {
+        return Some(candidate);
+    }
+
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (file_name, String::new()),
+    };
+
+    match policy {
+        CollisionPolicy::Skip => None,
+        CollisionPolicy::Timestamp => {
+            let stamp = Local::now().format("%H%M%S");
+            let name = format!("{}-{}{}", stem, stamp, extension);
+            destination_with(dir, &name, CollisionPolicy::Number, taken)
+        }
+        CollisionPolicy::Number => (1..)
+            .map(|index| dir.join(format!("{} ({}){}", stem, index, extension)))
+            .find(|candidate| !taken(candidate)),
+    }
+}
+
+/// 按归档规则将文件移入指定配置今天的文件夹（按需创建），返回移动后的路径；
+/// 被规则忽略或因重名跳过时返回 `None`
+pub fn file_into_today(settings: &AppSettings, file: &Path, journal: &Journal) -> AppResult<Option<PathBuf>> {
+    let RuleOutcome::Move { subfolder } =
+        rules::evaluate(&settings.rules, &FileFacts::from_path(file))?.outcome
+    else {
+        return Ok(None);
+    };
+
+    let inbox = &settings.inbox;
+    let profile = settings.profile(inbox.profile_id.as_deref())?;
+    let today = Local::now().date_naive();
+    let folder = PathBuf::from(FolderManager::create_folder_for_date(profile, &today, journal)?).join(subfolder);
+    let mut operation = Operation::new(OperationKind::Inbox);
+    journal::create_dirs(&folder, &mut operation.entries)?;
+
+    let file_name = file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| AppError::invalid_path("文件名无效"))?;
+    let Some(target) = destination(&folder, file_name, inbox.collision) else {
+        journal.record(&operation);
+        return Ok(None);
+    };
+
+    let moved = fs_util::move_file(file, &target)
+        .map_err(|e| AppError::file_system(&format!("移动文件失败: {}", e)));
+    if moved.is_ok() {
+        operation.entries.push(Entry::moved(file, &target));
+    }
+    journal.record(&operation);
+    moved.map(|_| Some(target))
+}
+
+/// 在后台监视来源目录；设置中的来源变化时自动重新监视
+pub fn start<R: Runtime>(app: tauri::AppHandle<R>) {
+    std::thread::spawn(move || {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("Inbox: failed to create watcher: {}", e);
+                return;
+            }
+        };
+
+        let mut watched: Vec<PathBuf> = Vec::new();
+        let mut pending = PendingFiles::default();
+
+        loop {
+            let Some(settings) = current_settings(&app) else {
+                std::thread::sleep(TICK);
+                continue;
+            };
+            let inbox = &settings.inbox;
+
+            // 来源目录随设置变化
+            let wanted: Vec<PathBuf> = if inbox.enabled {
+                inbox.sources.iter().map(PathBuf::from).collect()
+            } else {
+                Vec::new()
+            };
+            if wanted != watched {
+                for dir in &watched {
+                    let _ = watcher.unwatch(dir);
+                }
+                for dir in &wanted {
+                    if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                        eprintln!("Inbox: cannot watch {}: {}", dir.display(), e);
+                    }
+                }
+                watched = wanted;
+                pending.clear();
+            }
+
+            // 收集这段时间内新出现或被改写的文件
+            let deadline = Instant::now() + TICK;
+            while let Ok(event) = rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                let Ok(event) = event else {
+                    continue;
+                };
+                if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) || is_paused() {
+                    continue;
+                }
+                for path in event.paths {
+                    let in_source = path.parent().is_some_and(|parent| watched.iter().any(|dir| dir == parent));
+                    if in_source && !inbox.is_ignored(&path) {
+                        pending.observe(path, Instant::now());
+                    }
+                }
+            }
+
+            if is_paused() {
+                pending.clear();
+                continue;
+            }
+
+            let stable_for = Duration::from_secs(inbox.stable_secs);
+            for file in pending.take_stable(Instant::now(), stable_for) {
+                match Journal::global().and_then(|journal| file_into_today(&settings, &file, &journal)) {
+                    Ok(Some(target)) => {
+                        println!("Inbox: moved {} to {}", file.display(), target.display());
+                        let _ = app.emit(
+                            INBOX_FILE_MOVED_EVENT,
+                            MovedFile {
+                                from: file.to_string_lossy().to_string(),
+                                to: target.to_string_lossy().to_string(),
+                            },
+                        );
+                    }
+                    Ok(None) => println!("Inbox: left {} in place", file.display()),
+                    Err(e) => eprintln!("Inbox: failed to move {}: {}", file.display(), e),
+                }
+            }
+        }
+    });
+}
+
+/// 托盘开关：切换暂停状态并刷新菜单
+pub fn toggle_paused<R: Runtime>(app: &tauri::AppHandle<R>) {
+    set_paused(!is_paused());
+    if let Some(settings) = current_settings(app) {
+        tray::refresh(app, &settings);
+    }
+}
+
+fn current_settings<R: Runtime>(app: &tauri::AppHandle<R>) -> Option<AppSettings> {
+    let state = app.state::<Mutex<AppSettings>>();
+    let settings = state.lock().ok()?;
+    Some(settings.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Rule, RuleAction};
+    use crate::settings::{DateFormat, Profile};
+
+    #[test]
+    fn test_ignored_files() {
+        let inbox = InboxSettings::default();
+        assert!(inbox.is_ignored(Path::new("/dl/movie.mp4.crdownload")));
+        assert!(inbox.is_ignored(Path::new("/dl/archive.PART")));
+        assert!(inbox.is_ignored(Path::new("/dl/.DS_Store")));
+        assert!(!inbox.is_ignored(Path::new("/dl/report.pdf")));
+    }
+
+    #[test]
+    fn test_pending_files_wait_until_stable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("report.pdf");
+        fs::write(&file, "partial").unwrap();
+
+        let stable_for = Duration::from_secs(2);
+        let start = Instant::now();
+        let mut pending = PendingFiles::default();
+        pending.observe(file.clone(), start);
+
+        // 第一次检查只记录大小，之后还在写入则重新计时
+        assert!(pending.take_stable(start, stable_for).is_empty());
+        fs::write(&file, "partial and more").unwrap();
+        assert!(pending.take_stable(start + Duration::from_secs(1), stable_for).is_empty());
+        assert!(pending.take_stable(start + Duration::from_secs(2), stable_for).is_empty());
+        assert_eq!(pending.take_stable(start + Duration::from_secs(3), stable_for), vec![file.clone()]);
+
+        // 被删掉的文件直接丢弃
+        pending.observe(temp_dir.path().join("gone.pdf"), start);
+        assert!(pending.take_stable(start + Duration::from_secs(9), stable_for).is_empty());
+        assert!(pending.files.is_empty());
+    }
+
+    #[test]
+    fn test_destination_collisions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        assert_eq!(destination(dir, "report.pdf", CollisionPolicy::Skip), Some(dir.join("report.pdf")));
+
+        fs::write(dir.join("report.pdf"), "").unwrap();
+        fs::write(dir.join("report (1).pdf"), "").unwrap();
+        fs::write(dir.join("README"), "").unwrap();
+        assert_eq!(destination(dir, "report.pdf", CollisionPolicy::Number), Some(dir.join("report (2).pdf")));
+        assert_eq!(destination(dir, "README", CollisionPolicy::Number), Some(dir.join("README (1)")));
+        assert_eq!(destination(dir, "report.pdf", CollisionPolicy::Skip), None);
+
+        let stamped = destination(dir, "report.pdf", CollisionPolicy::Timestamp).unwrap();
+        let name = stamped.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("report-") && name.ends_with(".pdf"));
+    }
+
+    #[test]
+    fn test_file_into_today_creates_folder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let downloads = temp_dir.path().join("Downloads");
+        let daily = temp_dir.path().join("Daily");
+        fs::create_dir_all(&downloads).unwrap();
+        fs::create_dir_all(&daily).unwrap();
+        let journal = Journal::at(temp_dir.path().join(journal::JOURNAL_FILE));
+
+        let settings = AppSettings {
+            profiles: vec![Profile {
+                folder_path: daily.to_string_lossy().to_string(),
+                date_format: DateFormat::YYYYMMDD,
+                ..Profile::default()
+            }],
+            ..AppSettings::default()
+        };
+
+        for _ in 0..2 {
+            fs::write(downloads.join("invoice.pdf"), "pdf").unwrap();
+            file_into_today(&settings, &downloads.join("invoice.pdf"), &journal).unwrap().unwrap();
+        }
+
+        let today = FolderManager::folder_path_for_date(&settings.profiles[0], &Local::now().date_naive()).unwrap();
+        assert!(today.join("invoice.pdf").is_file());
+        assert!(today.join("invoice (1).pdf").is_file());
+        assert!(!downloads.join("invoice.pdf").exists());
+
+        // 按规则放进子目录或留在原处
+        let settings = AppSettings {
+            rules: vec![
+                Rule {
+                    extensions: vec!["pdf".to_string()],
+                    action: RuleAction::MoveTo { subfolder: "docs".to_string() },
+                    ..Rule::default()
+                },
+                Rule { action: RuleAction::Ignore, ..Rule::default() },
+            ],
+            ..settings
+        };
+        fs::write(downloads.join("paper.pdf"), "pdf").unwrap();
+        fs::write(downloads.join("setup.exe"), "exe").unwrap();
+        file_into_today(&settings, &downloads.join("paper.pdf"), &journal).unwrap().unwrap();
+        assert!(file_into_today(&settings, &downloads.join("setup.exe"), &journal).unwrap().is_none());
+        assert!(today.join("docs").join("paper.pdf").is_file());
+        assert!(downloads.join("setup.exe").is_file());
+    }
+}
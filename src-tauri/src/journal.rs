@@ -0,0 +1,441 @@
+//! 操作日志：按行追加记录每次新建、移动和重命名，保存在应用数据目录，供撤销使用
+
+use crate::error::{AppError, AppResult};
+use crate::fs_util;
+use crate::overview::META_FILE;
+use crate::settings::APP_IDENTIFIER;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 日志文件名，位于应用数据目录
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// 同一进程内的追加和撤销依次进行
+static JOURNAL_LOCK: Mutex<()> = Mutex::new(());
+
+/// 一次完整的文件操作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: String,
+    pub kind: OperationKind,
+    pub at: DateTime<Local>,
+    /// 按执行顺序记录的每一步
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OperationKind {
+    /// 新建日期文件夹（含上级目录和脚手架）
+    CreateFolder { profile_id: String, date: NaiveDate },
+    /// 收件箱归档了一个文件
+    Inbox,
+    /// 按日期整理某个目录
+    Organize { source_dir: PathBuf },
+    /// 把旧文件夹移到归档目录
+    Retention,
+    /// 命名格式改变后按新格式重命名已有的日期文件夹
+    RenameFolders { profile_id: String },
+    /// 撤销了另一个操作
+    Undo { operation_id: String },
+}
+
+/// 操作中的一步；`fingerprint` 是文件在这一步之后的状态，撤销前据此判断文件是否被改过
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Entry {
+    Create {
+        path: PathBuf,
+        at: DateTime<Local>,
+        fingerprint: Option<Fingerprint>,
+    },
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+        at: DateTime<Local>,
+        fingerprint: Option<Fingerprint>,
+    },
+    /// 在同一目录中改名
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        at: DateTime<Local>,
+        fingerprint: Option<Fingerprint>,
+    },
+    /// 撤销新建时删除
+    Remove { path: PathBuf, at: DateTime<Local> },
+}
+
+/// 文件大小和修改时间；目录没有指纹
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub len: u64,
+    pub modified: Option<DateTime<Local>>,
+}
+
+impl Fingerprint {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok().filter(|metadata| metadata.is_file())?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::from),
+        })
+    }
+}
+
+impl Entry {
+    /// 记录已新建的文件或目录
+    pub fn created(path: &Path) -> Self {
+        Entry::Create {
+            path: path.to_path_buf(),
+            at: Local::now(),
+            fingerprint: Fingerprint::of(path),
+        }
+    }
+
+    /// 记录已完成的移动；目录相同时记为重命名
+    pub fn moved(from: &Path, to: &Path) -> Self {
+        let (from, to, at, fingerprint) = (from.to_path_buf(), to.to_path_buf(), Local::now(), Fingerprint::of(to));
+        if from.parent() == to.parent() {
+            Entry::Rename { from, to, at, fingerprint }
+        } else {
+            Entry::Move { from, to, at, fingerprint }
+        }
+    }
+
+    /// 这一步之后存在的路径
+    fn target(&self) -> &Path {
+        match self {
+            Entry::Create { path, .. } | Entry::Remove { path, .. } => path,
+            Entry::Move { to, .. } | Entry::Rename { to, .. } => to,
+        }
+    }
+}
+
+impl Operation {
+    pub fn new(kind: OperationKind) -> Self {
+        let at = Local::now();
+        let mut suffix = [0u8; 2];
+        let _ = getrandom::fill(&mut suffix);
+        Self {
+            id: format!("{}-{:02x}{:02x}", at.format("%Y%m%d%H%M%S%3f"), suffix[0], suffix[1]),
+            kind,
+            at,
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// 追加写入的操作日志
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// 应用数据目录中的日志，与 Tauri 的 `app_data_dir` 相同，命令行模式也写入这里
+    pub fn global() -> AppResult<Self> {
+        dirs::data_dir()
+            .map(|dir| Self::at(dir.join(APP_IDENTIFIER).join(JOURNAL_FILE)))
+            .ok_or_else(|| AppError::configuration("无法获取应用数据目录"))
+    }
+
+    /// 追加一条操作记录；失败只输出警告，不影响已经完成的文件操作
+    pub fn record(&self, operation: &Operation) {
+        if operation.entries.is_empty() {
+            return;
+        }
+        if let Err(e) = self.append(operation) {
+            eprintln!("Warning: failed to record operation {}: {}", operation.id, e);
+        }
+    }
+
+    /// 追加一条操作记录并落盘
+    pub fn append(&self, operation: &Operation) -> AppResult<()> {
+        let _guard = JOURNAL_LOCK.lock();
+        self.write(operation)
+    }
+
+    fn write(&self, operation: &Operation) -> AppResult<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut line = serde_json::to_string(operation)
+            .map_err(|e| AppError::file_system(&format!("无法写入操作日志: {}", e)))?;
+        line.push('\n');
+
+        // 整行一次写入，追加模式下不会与其他进程的记录交错
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// 读取全部操作，最早的在前；写到一半的行被跳过
+    pub fn operations(&self) -> AppResult<Vec<Operation>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(operation) => Some(operation),
+                Err(e) => {
+                    eprintln!("Skipping unreadable journal entry: {}", e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// 撤销最近一个尚未撤销的操作
+    pub fn undo_last(&self) -> AppResult<Operation> {
+        let _guard = JOURNAL_LOCK.lock();
+        let operations = self.operations()?;
+        let undone = undone_ids(&operations);
+        let last = operations
+            .iter()
+            .rev()
+            .find(|operation| {
+                !matches!(operation.kind, OperationKind::Undo { .. }) && !undone.contains(operation.id.as_str())
+            })
+            .ok_or_else(|| AppError::configuration("没有可以撤销的操作"))?;
+        self.reverse(last)
+    }
+
+    /// 撤销一个操作：删除新建的文件和目录，把移动过的文件移回原处
+    ///
+    /// 任何一步的结果已被改动（文件被修改或移走、目录中有了新内容、原位置已被占用）时，
+    /// 整个撤销都会被拒绝，不修改任何文件。
+    pub fn undo(&self, operation_id: &str) -> AppResult<Operation> {
+        let _guard = JOURNAL_LOCK.lock();
+        let operations = self.operations()?;
+        let operation = operations
+            .iter()
+            .find(|operation| operation.id == operation_id)
+            .ok_or_else(|| AppError::configuration(&format!("找不到操作: {}", operation_id)))?;
+
+        if matches!(operation.kind, OperationKind::Undo { .. }) {
+            return Err(AppError::configuration("撤销操作本身不能再撤销"));
+        }
+        if undone_ids(&operations).contains(operation_id) {
+            return Err(AppError::configuration("该操作已经撤销"));
+        }
+        self.reverse(operation)
+    }
+
+    fn reverse(&self, operation: &Operation) -> AppResult<Operation> {
+        check_unchanged(operation)?;
+
+        let mut undo = Operation::new(OperationKind::Undo {
+            operation_id: operation.id.clone(),
+        });
+        for entry in operation.entries.iter().rev() {
+            let result = match entry {
+                Entry::Create { path, fingerprint: Some(_), .. } => fs::remove_file(path),
+                Entry::Create { path, fingerprint: None, .. } => remove_meta(path).and_then(|_| fs::remove_dir(path)),
+                Entry::Move { from, to, .. } | Entry::Rename { from, to, .. } => {
+                    from.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| {
+                        if to.is_dir() {
+                            fs_util::move_dir(to, from)
+                        } else {
+                            fs_util::move_file(to, from)
+                        }
+                    })
+                }
+                Entry::Remove { .. } => Ok(()),
+            };
+
+            if let Err(e) = result {
+                // 已完成的部分仍然记入日志
+                let _ = self.write(&undo);
+                return Err(AppError::file_system(&format!(
+                    "撤销中途失败，已完成 {} 步: {}",
+                    undo.entries.len(),
+                    e
+                )));
+            }
+            undo.entries.push(match entry {
+                Entry::Create { path, .. } => Entry::Remove { path: path.clone(), at: Local::now() },
+                Entry::Move { from, to, .. } | Entry::Rename { from, to, .. } => Entry::moved(to, from),
+                Entry::Remove { path, at } => Entry::Remove { path: path.clone(), at: *at },
+            });
+        }
+
+        self.write(&undo)?;
+        eprintln!("Undid operation {}", operation.id);
+        Ok(undo)
+    }
+}
+
+/// 创建 `dir` 及缺失的上级目录，并把新建的每一级（由外到内）记入 `entries`
+pub fn create_dirs(dir: &Path, entries: &mut Vec<Entry>) -> AppResult<()> {
+    let mut missing: Vec<PathBuf> = dir
+        .ancestors()
+        .take_while(|ancestor| !ancestor.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    fs::create_dir_all(dir)
+        .map_err(|e| AppError::file_system(&format!("创建文件夹失败: {}", e)))?;
+
+    missing.reverse();
+    entries.extend(missing.iter().map(|path| Entry::created(path)));
+    Ok(())
+}
+
+/// 删除概览写入目录的标签和备注记录，它不算目录中的新内容
+fn remove_meta(dir: &Path) -> std::io::Result<()> {
+    match fs::remove_file(dir.join(META_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn undone_ids(operations: &[Operation]) -> HashSet<&str> {
+    operations
+        .iter()
+        .filter_map(|operation| match &operation.kind {
+            OperationKind::Undo { operation_id } => Some(operation_id.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 确认操作的每一步结果都还保持原样
+fn check_unchanged(operation: &Operation) -> AppResult<()> {
+    let owned: HashSet<&Path> = operation.entries.iter().map(Entry::target).collect();
+    let refuse = |reason: &str, path: &Path| {
+        Err(AppError::invalid_path(&format!("无法撤销，{}: {}", reason, path.display())))
+    };
+
+    for entry in &operation.entries {
+        match entry {
+            Entry::Create { path, fingerprint: None, .. } => {
+                let Ok(children) = fs::read_dir(path) else {
+                    return refuse("目录已不存在", path);
+                };
+                let foreign = children
+                    .filter_map(|child| child.ok())
+                    .any(|child| child.file_name() != META_FILE && !owned.contains(child.path().as_path()));
+                if foreign {
+                    return refuse("目录中有了新内容", path);
+                }
+            }
+            Entry::Create { path, fingerprint, .. } => {
+                if Fingerprint::of(path) != *fingerprint {
+                    return refuse("文件已被修改或移走", path);
+                }
+            }
+            Entry::Move { from, to, fingerprint, .. } | Entry::Rename { from, to, fingerprint, .. } => {
+                let unchanged = match fingerprint {
+                    Some(_) => Fingerprint::of(to) == *fingerprint,
+                    None => to.is_dir(),
+                };
+                if !unchanged {
+                    return refuse("文件已被修改或移走", to);
+                }
+                if from.exists() && !owned.contains(from.as_path()) {
+                    return refuse("原位置已有文件", from);
+                }
+            }
+            Entry::Remove { .. } => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 把 `name` 移入 `dir`（按需创建），返回记录了这两步的操作
+    fn move_into(name: &Path, dir: &Path) -> Operation {
+        let mut operation = Operation::new(OperationKind::Inbox);
+        create_dirs(dir, &mut operation.entries).unwrap();
+        let to = dir.join(name.file_name().unwrap());
+        fs::rename(name, &to).unwrap();
+        operation.entries.push(Entry::moved(name, &to));
+        operation
+    }
+
+    #[test]
+    fn test_undo_refuses_when_files_changed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal = Journal::at(temp_dir.path().join(JOURNAL_FILE));
+        let from = temp_dir.path().join("a.txt");
+        let to = temp_dir.path().join("2024-03-15").join("a.txt");
+        fs::write(&from, "a").unwrap();
+        let operation = move_into(&from, to.parent().unwrap());
+        assert!(matches!(operation.entries[1], Entry::Move { fingerprint: Some(_), .. }));
+        journal.append(&operation).unwrap();
+
+        // 原位置被占用时拒绝撤销，不移动任何文件
+        fs::write(&from, "new").unwrap();
+        assert!(journal.undo(&operation.id).is_err());
+        assert!(to.is_file());
+        fs::remove_file(&from).unwrap();
+
+        let undo = journal.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&from).unwrap(), "a");
+        assert!(!to.parent().unwrap().exists());
+        assert_eq!(undo.entries.len(), 2);
+        assert!(journal.undo(&undo.id).is_err());
+        assert!(journal.undo(&operation.id).is_err());
+        assert!(journal.undo_last().is_err());
+        assert_eq!(journal.operations().unwrap().len(), 2);
+
+        // 移动后文件被修改过
+        let operation = move_into(&from, to.parent().unwrap());
+        journal.append(&operation).unwrap();
+        fs::write(&to, "changed").unwrap();
+        assert!(journal.undo_last().unwrap_err().message.contains("已被修改"));
+        assert!(to.is_file());
+    }
+
+    #[test]
+    fn test_rename_and_created_dirs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal = Journal::at(temp_dir.path().join(JOURNAL_FILE));
+
+        let mut created = Operation::new(OperationKind::Inbox);
+        let dir = temp_dir.path().join("0315");
+        create_dirs(&dir, &mut created.entries).unwrap();
+        fs::write(dir.join("notes.md"), "# notes").unwrap();
+        created.entries.push(Entry::created(&dir.join("notes.md")));
+        journal.append(&created).unwrap();
+
+        let mut renamed = Operation::new(OperationKind::Inbox);
+        let new_name = temp_dir.path().join("2024-03-15");
+        fs::rename(&dir, &new_name).unwrap();
+        renamed.entries.push(Entry::moved(&dir, &new_name));
+        assert!(matches!(renamed.entries[0], Entry::Rename { fingerprint: None, .. }));
+        journal.append(&renamed).unwrap();
+
+        // 依次撤销改名和新建
+        journal.undo_last().unwrap();
+        assert!(dir.join("notes.md").is_file());
+        assert!(!new_name.exists());
+
+        // 目录中出现了新文件时拒绝撤销
+        fs::write(dir.join("mine.txt"), "").unwrap();
+        assert!(journal.undo_last().unwrap_err().message.contains("新内容"));
+        fs::remove_file(dir.join("mine.txt")).unwrap();
+        // 概览的标签和备注记录随目录一起删除
+        fs::write(dir.join(META_FILE), "{}").unwrap();
+        journal.undo_last().unwrap();
+        assert!(!dir.exists());
+    }
+}
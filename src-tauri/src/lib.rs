@@ -1,13 +1,36 @@
 use std::sync::Mutex;
 use tauri::Manager;
 
+mod actions;
+mod calendar;
+pub mod cli;
 mod commands;
+mod config_watcher;
+mod control_api;
+mod datexpr;
+mod deep_link;
+mod diagnostics;
 mod error;
 mod folder;
+mod fs_util;
+mod hooks;
+mod inbox;
+mod journal;
+mod media_date;
+mod migration;
+mod organize;
+mod overview;
+mod rename;
+mod retention;
+mod rules;
+mod scaffold;
+mod scheduler;
 mod settings;
+mod template;
 mod tray;
 
 use commands::*;
+use diagnostics::StartupDiagnostics;
 use folder::FolderManager;
 use settings::AppSettings;
 
@@ -21,21 +44,38 @@ pub fn run() {
         ))
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
-            // 当尝试启动第二个实例时，显示主窗口
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            // 第二个实例带操作参数（如快捷方式的 --create-today）时交给已运行的实例执行，
+            // 链接由 deep-link 插件转发处理，其余情况显示主窗口
+            if deep_link::is_deep_link(&argv) || run_argv_action(app, &argv) {
+                return;
+            }
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
                 let _ = window.set_focus();
                 let _ = window.unminimize();
             }
         }))
+        .plugin(tauri_plugin_deep_link::init())
         .setup(|app| {
-            // 初始化设置
-            let settings = AppSettings::load(app.handle())
-                .unwrap_or_else(|_| AppSettings::default());
+            // 初始化设置，配置文件损坏时自动隔离并从备份恢复
+            let mut diagnostics = StartupDiagnostics::default();
+            let settings = match AppSettings::load_or_recover(app.handle()) {
+                Ok((settings, recovery)) => {
+                    diagnostics.config_recovery = recovery;
+                    settings
+                }
+                Err(e) => {
+                    eprintln!("Failed to load settings, using defaults: {}", e);
+                    diagnostics.config_error = Some(e);
+                    AppSettings::default()
+                }
+            };
             
             // 将设置存储到应用状态中
             app.manage(Mutex::new(settings.clone()));
+            app.manage(diagnostics);
+            app.manage(control_api::ControlApiState::default());
             
             // 创建系统托盘
             tray::create_tray(app.handle())?;
@@ -49,13 +89,38 @@ pub fn run() {
                 }
             }
             
-            // 如果启用了启动时自动创建，则创建今天的文件夹
-            if settings.auto_create_on_startup {
-                if let Err(e) = FolderManager::create_today_folder(&settings) {
-                    eprintln!("Failed to create today's folder on startup: {}", e);
+            // 为启用了自动创建的配置创建今天的文件夹（按工作日策略跳过节假日）
+            let today = chrono::Local::now().date_naive();
+            if settings.calendar.allows_auto_create(&today) {
+                for profile in settings.profiles.iter().filter(|profile| profile.auto_create) {
+                    let created = journal::Journal::global()
+                        .and_then(|journal| FolderManager::create_today_folder(profile, &journal));
+                    if let Err(e) = created {
+                        eprintln!("Failed to create today's folder for {} on startup: {}", profile.id, e);
+                    }
                 }
             }
             
+            // 启动后台调度器，应用持续运行时每天自动创建新文件夹
+            scheduler::start(app.handle().clone());
+            
+            // 监视收件箱来源目录，新文件移入今天的文件夹
+            inbox::start(app.handle().clone());
+            retention::start(app.handle().clone());
+            
+            // 按设置启动本地控制接口
+            control_api::sync(app.handle(), &settings);
+            
+            // 监听配置文件，手动编辑或同步后自动生效
+            config_watcher::start(app.handle().clone());
+            
+            // 处理 smartfolderzen:// 链接
+            deep_link::init(app.handle());
+            
+            // 首次启动时也执行参数中的操作
+            let argv: Vec<String> = std::env::args().collect();
+            run_argv_action(app.handle(), &argv);
+            
             // 隐藏主窗口（启动到托盘）
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.hide();
@@ -68,8 +133,28 @@ pub fn run() {
             open_folder_in_explorer,
             get_settings,
             save_settings,
+            get_startup_diagnostics,
+            get_hook_log,
+            test_rules,
+            plan_organize,
+            run_organize,
+            list_operations,
+            undo_operation,
+            undo_last,
+            plan_retention,
+            run_retention,
+            plan_rename_folders,
+            run_rename_folders,
+            scan_folders,
+            get_month_overview,
+            set_day_meta,
+            resolve_date,
+            open_date,
+            list_settings_backups,
+            restore_settings_backup,
             validate_folder_path,
             get_today_folder_status,
+            import_holiday_calendar,
             show_main_window,
             hide_main_window,
             quit_app,
@@ -81,3 +166,21 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+/// 执行启动参数中的操作，参数中包含操作（无论成败）时返回 true
+fn run_argv_action(app: &tauri::AppHandle, argv: &[String]) -> bool {
+    let today = chrono::Local::now().date_naive();
+    match actions::parse_argv(argv, today) {
+        Ok(Some(action)) => {
+            if let Err(e) = action.perform(app) {
+                eprintln!("Failed to run {:?}: {}", action, e);
+            }
+            true
+        }
+        Ok(None) => false,
+        Err(e) => {
+            eprintln!("Invalid launch arguments {:?}: {}", argv, e);
+            true
+        }
+    }
+}
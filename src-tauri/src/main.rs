@@ -2,5 +2,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // 带子命令启动时按命令行模式运行，不创建窗口
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if smart_folder_zen_lib::cli::is_cli_invocation(&args) {
+        std::process::exit(smart_folder_zen_lib::cli::run(&args));
+    }
+
     smart_folder_zen_lib::run()
 }
@@ -0,0 +1,156 @@
+//! 照片和视频的拍摄日期：JPEG/HEIC/TIFF 的 EXIF `DateTimeOriginal`，MP4/MOV 的 `mvhd` 创建时间
+//!
+//! 复制文件会重置修改时间，按拍摄日期整理相机和手机导出的文件更可靠。
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, TimeZone, Utc};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// 带 EXIF 的图片格式
+const EXIF_EXTENSIONS: &[&str] = &["jpg", "jpeg", "heic", "heif", "tif", "tiff", "dng", "png", "webp"];
+/// ISO BMFF / QuickTime 视频格式
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "m4v", "3gp"];
+
+/// 读取文件的拍摄日期；不是支持的格式或没有记录时返回 `None`
+pub fn capture_date(path: &Path) -> Option<NaiveDate> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mut reader = BufReader::new(File::open(path).ok()?);
+
+    if EXIF_EXTENSIONS.contains(&extension.as_str()) {
+        exif_date(&mut reader)
+    } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+        video_date(&mut reader)
+    } else {
+        None
+    }
+}
+
+/// EXIF 中的原始拍摄时间，按相机记录的本地时间取日期
+fn exif_date(reader: &mut BufReader<File>) -> Option<NaiveDate> {
+    let exif = exif::Reader::new().read_from_container(reader).ok()?;
+    let field = exif.get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)?;
+    let exif::Value::Ascii(values) = &field.value else {
+        return None;
+    };
+    let date_time = exif::DateTime::from_ascii(values.first()?).ok()?;
+    NaiveDate::from_ymd_opt(date_time.year.into(), date_time.month.into(), date_time.day.into())
+}
+
+/// `moov/mvhd` 中的创建时间：自 1904-01-01 UTC 起的秒数，0 表示未记录
+fn video_date(reader: &mut (impl Read + Seek)) -> Option<NaiveDate> {
+    let file_end = reader.seek(SeekFrom::End(0)).ok()?;
+    reader.seek(SeekFrom::Start(0)).ok()?;
+
+    let moov_end = find_box(reader, file_end, b"moov")?;
+    find_box(reader, moov_end, b"mvhd")?;
+
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version).ok()?;
+    let seconds = if version[0] == 1 {
+        let mut bytes = [0u8; 8];
+        reader.read_exact(&mut bytes).ok()?;
+        u64::from_be_bytes(bytes)
+    } else {
+        let mut bytes = [0u8; 4];
+        reader.read_exact(&mut bytes).ok()?;
+        u32::from_be_bytes(bytes).into()
+    };
+    if seconds == 0 {
+        return None;
+    }
+
+    let epoch = Utc.with_ymd_and_hms(1904, 1, 1, 0, 0, 0).single()?;
+    let created = epoch.checked_add_signed(TimeDelta::try_seconds(seconds.try_into().ok()?)?)?;
+    Some(DateTime::<Local>::from(created).date_naive())
+}
+
+/// 从当前位置起逐个跳过 box，直到找到指定类型；返回该 box 的结束位置，读取位置停在其内容开头
+fn find_box(reader: &mut (impl Read + Seek), end: u64, kind: &[u8; 4]) -> Option<u64> {
+    loop {
+        let start = reader.stream_position().ok()?;
+        if start + 8 > end {
+            return None;
+        }
+
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header).ok()?;
+        let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let box_end = match size {
+            0 => end,
+            1 => {
+                let mut large = [0u8; 8];
+                reader.read_exact(&mut large).ok()?;
+                start.checked_add(u64::from_be_bytes(large))?
+            }
+            size => start + u64::from(size),
+        };
+        if box_end <= start || box_end > end {
+            return None;
+        }
+
+        if &header[4..8] == kind {
+            return Some(box_end);
+        }
+        reader.seek(SeekFrom::Start(box_end)).ok()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(kind: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut bytes = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(content);
+        bytes
+    }
+
+    #[test]
+    fn test_video_date() {
+        // 2024-03-15 12:00:00 UTC，距 1904-01-01 的秒数
+        let seconds = (Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()
+            - Utc.with_ymd_and_hms(1904, 1, 1, 0, 0, 0).unwrap())
+        .num_seconds() as u32;
+        let mut mvhd = vec![0, 0, 0, 0];
+        mvhd.extend_from_slice(&seconds.to_be_bytes());
+        mvhd.extend_from_slice(&[0; 16]);
+
+        let mut file = mp4_box(b"ftyp", b"qt  ");
+        file.extend(mp4_box(b"mdat", &[0; 64]));
+        file.extend(mp4_box(b"moov", &[mp4_box(b"trak", &[]), mp4_box(b"mvhd", &mvhd)].concat()));
+
+        let expected = DateTime::<Local>::from(Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap()).date_naive();
+        assert_eq!(video_date(&mut Cursor::new(&file)), Some(expected));
+
+        // 未记录创建时间或文件被截断
+        let unset = [mp4_box(b"ftyp", b"isom"), mp4_box(b"moov", &mp4_box(b"mvhd", &[0; 24]))].concat();
+        assert_eq!(video_date(&mut Cursor::new(&unset)), None);
+        assert_eq!(video_date(&mut Cursor::new(&file[..file.len() - 10])), None);
+    }
+
+    #[test]
+    fn test_exif_date() {
+        let field = exif::Field {
+            tag: exif::Tag::DateTimeOriginal,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Ascii(vec![b"2023:07:01 23:30:00".to_vec()]),
+        };
+        let mut writer = exif::experimental::Writer::new();
+        writer.push_field(&field);
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let photo = temp_dir.path().join("IMG_0001.TIF");
+        std::fs::write(&photo, tiff.into_inner()).unwrap();
+        assert_eq!(capture_date(&photo), NaiveDate::from_ymd_opt(2023, 7, 1));
+
+        let no_exif = temp_dir.path().join("broken.jpg");
+        std::fs::write(&no_exif, b"not a jpeg").unwrap();
+        assert_eq!(capture_date(&no_exif), None);
+        assert_eq!(capture_date(&temp_dir.path().join("notes.txt")), None);
+    }
+}
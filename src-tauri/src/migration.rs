@@ -0,0 +1,192 @@
+use crate::error::{AppError, AppResult};
+use crate::settings::DEFAULT_PROFILE_ID;
+use serde_json::{Map, Value};
+
+/// 当前配置文件的结构版本
+///
+/// - 1: 单个文件夹配置（`folder_path`、`date_format` 等位于顶层），没有 `version` 字段
+/// - 2: 多配置（`profiles` 列表）
+pub const CURRENT_VERSION: u32 = 2;
+
+/// 依次执行的升级步骤，第 i 项将版本 i + 1 升级到 i + 2
+const MIGRATIONS: &[fn(&mut Map<String, Value>)] = &[migrate_v1_to_v2];
+
+/// 识别配置文件的结构版本；早期版本没有 `version` 字段，按结构推断
+pub fn detect_version(value: &Value) -> AppResult<u32> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| AppError::configuration("配置文件格式错误: 顶层必须是对象"))?;
+
+    match object.get("version") {
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| AppError::configuration(&format!("配置文件版本无效: {}", version))),
+        None if object.contains_key("profiles") => Ok(2),
+        None => Ok(1),
+    }
+}
+
+/// 将配置升级到当前版本
+///
+/// 有改动时返回升级前的版本，调用方据此备份并写回；已是当前版本时返回 `None`。
+/// 高于当前版本的配置来自更新的程序，按旧结构读取再保存会丢失新字段，因此直接报错。
+pub fn migrate(value: &mut Value) -> AppResult<Option<u32>> {
+    let from_version = detect_version(value)?;
+    if from_version > CURRENT_VERSION {
+        return Err(AppError::configuration(&format!(
+            "配置文件版本 {} 来自更新版本的程序，当前程序只支持到版本 {}",
+            from_version, CURRENT_VERSION
+        )));
+    }
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| AppError::configuration("配置文件格式错误: 顶层必须是对象"))?;
+
+    if from_version == CURRENT_VERSION && object.contains_key("version") {
+        return Ok(None);
+    }
+
+    for migration in MIGRATIONS.iter().skip(from_version as usize - 1) {
+        migration(object);
+    }
+    object.insert("version".into(), CURRENT_VERSION.into());
+
+    Ok(Some(from_version))
+}
+
+/// v1 → v2：将顶层的单个文件夹配置移入 `profiles` 列表
+fn migrate_v1_to_v2(object: &mut Map<String, Value>) {
+    let mut profile = Map::new();
+    profile.insert("id".into(), DEFAULT_PROFILE_ID.into());
+    profile.insert("name".into(), "默认".into());
+    for key in ["folder_path", "date_format", "folder_levels", "scaffold"] {
+        if let Some(field) = object.remove(key) {
+            profile.insert(key.into(), field);
+        }
+    }
+    let auto_create = object
+        .remove("auto_create_on_startup")
+        .unwrap_or(Value::Bool(true));
+    profile.insert("auto_create".into(), auto_create);
+
+    object.insert("profiles".into(), Value::Array(vec![profile.into()]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{AppSettings, DateFormat};
+    use std::fs;
+    use tempfile::tempdir;
+
+    /// 各历史版本的配置文件样本
+    const FIXTURES: &[(&str, &str, u32)] = &[
+        (
+            "v1_baseline",
+            include_str!("../tests/fixtures/settings/v1_baseline.json"),
+            1,
+        ),
+        (
+            "v1_extended",
+            include_str!("../tests/fixtures/settings/v1_extended.json"),
+            1,
+        ),
+        (
+            "v2_unversioned",
+            include_str!("../tests/fixtures/settings/v2_unversioned.json"),
+            2,
+        ),
+        ("v2", include_str!("../tests/fixtures/settings/v2.json"), 2),
+    ];
+
+    #[test]
+    fn test_load_every_historical_version() {
+        for (name, content, version) in FIXTURES {
+            let temp_dir = tempdir().unwrap();
+            let config_path = temp_dir.path().join("settings.json");
+            fs::write(&config_path, content).unwrap();
+
+            // 只读加载不写回
+            assert!(AppSettings::read_from(&config_path).is_ok(), "{}", name);
+            assert_eq!(fs::read_to_string(&config_path).unwrap(), *content, "{}", name);
+
+            let settings = AppSettings::load_from(&config_path)
+                .unwrap_or_else(|e| panic!("{} failed to load: {}", name, e));
+            let profile = settings.profile(None).unwrap();
+
+            assert_eq!(settings.version, CURRENT_VERSION, "{}", name);
+            assert_eq!(profile.folder_path, "D:\\Daily", "{}", name);
+            assert!(matches!(profile.date_format, DateFormat::MMDD), "{}", name);
+            assert!(!profile.auto_create, "{}", name);
+
+            // 升级后的文件已写回，旧文件留有备份
+            let saved: Value =
+                serde_json::from_str(&fs::read_to_string(&config_path).unwrap()).unwrap();
+            assert_eq!(saved["version"], CURRENT_VERSION, "{}", name);
+
+            let backup = temp_dir.path().join(format!("settings.json.v{}.bak", version));
+            if !content.contains("\"version\"") {
+                assert_eq!(fs::read_to_string(backup).unwrap(), *content, "{}", name);
+            } else {
+                assert!(!backup.exists(), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_extended_fields_survive_migration() {
+        let mut value: Value = serde_json::from_str(FIXTURES[1].1).unwrap();
+        assert_eq!(migrate(&mut value).unwrap(), Some(1));
+
+        let settings: AppSettings = serde_json::from_value(value).unwrap();
+        let profile = settings.profile(None).unwrap();
+        assert_eq!(profile.folder_levels.len(), 2);
+        assert_eq!(profile.scaffold.len(), 1);
+        assert!(settings.calendar.workdays_only);
+    }
+
+    #[test]
+    fn test_unknown_and_missing_fields_are_tolerated() {
+        let mut value = serde_json::json!({
+            "version": 2,
+            "profiles": [{ "id": "work", "folder_path": "D:\\Work", "color": "blue" }],
+            "theme": "dark"
+        });
+        assert_eq!(migrate(&mut value).unwrap(), None);
+
+        let settings: AppSettings = serde_json::from_value(value).unwrap();
+        assert!(settings.auto_start);
+        assert_eq!(settings.profile(Some("work")).unwrap().folder_path, "D:\\Work");
+    }
+
+    #[test]
+    fn test_rejects_invalid_version() {
+        assert!(detect_version(&serde_json::json!({ "version": "two" })).is_err());
+        assert!(detect_version(&serde_json::json!({ "version": 0 })).is_err());
+        assert!(detect_version(&serde_json::json!([])).is_err());
+        assert_eq!(detect_version(&serde_json::json!({ "version": 9 })).unwrap(), 9);
+    }
+
+    #[test]
+    fn test_newer_version_is_not_overwritten() {
+        let newer = serde_json::json!({ "version": CURRENT_VERSION + 1, "profiles": [], "theme": "dark" });
+        assert!(migrate(&mut newer.clone()).is_err());
+
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+        let content = newer.to_string();
+        fs::write(&config_path, &content).unwrap();
+        assert!(AppSettings::read_from(&config_path).is_err());
+        assert!(AppSettings::reload_from(&config_path).is_err());
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), content);
+
+        // 启动时隔离原文件，原内容保持不变
+        let (settings, recovery) = AppSettings::load_or_recover_from(&config_path).unwrap();
+        let recovery = recovery.unwrap();
+        assert_eq!(settings.version, CURRENT_VERSION);
+        assert!(recovery.error.message.contains("更新版本"));
+        assert_eq!(fs::read_to_string(&recovery.quarantined_path).unwrap(), content);
+    }
+}
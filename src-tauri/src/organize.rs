@@ -0,0 +1,366 @@
+//! 按日期整理：把下载目录、桌面等处散落的文件一次性移入对应日期的文件夹
+//!
+//! 先用 [`plan`] 生成移动计划供预览，确认后 [`execute`] 执行并写入操作日志，可整体撤销。
+
+use crate::error::{AppError, AppResult};
+use crate::folder::FolderManager;
+use crate::fs_util;
+use crate::inbox::{self, CollisionPolicy};
+use crate::journal::{self, Entry, Journal, Operation, OperationKind};
+use crate::media_date;
+use crate::settings::Profile;
+use chrono::{DateTime, Local, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// 执行中每移动一个文件发出一次，负载为 [`OrganizeProgress`]
+pub const ORGANIZE_PROGRESS_EVENT: &str = "organize-progress";
+
+/// 文件名中的日期：`IMG_20240315_…`、`Screenshot 2024-03-15 …`、`2024_03_15.log`
+static FILE_NAME_DATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\D)((?:19|20)\d{2})([-_.]?)(\d{2})([-_.]?)(\d{2})(?:\D|$)")
+        .expect("static regex is valid")
+});
+
+/// 按哪个日期归入文件夹
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateSource {
+    /// 拍摄日期：照片的 EXIF 原始拍摄时间或视频的创建时间
+    Metadata,
+    /// 文件名中的日期
+    FileName,
+    /// 修改时间
+    Modified,
+    /// 创建时间，部分文件系统不支持
+    Created,
+}
+
+/// 默认先看拍摄日期和文件名，最后退回修改时间
+fn default_date_sources() -> Vec<DateSource> {
+    vec![DateSource::Metadata, DateSource::FileName, DateSource::Modified]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizeOptions {
+    /// 要整理的目录，只处理直接位于其中的文件
+    pub source_dir: String,
+    /// 移入哪个配置的日期文件夹，为空时使用默认配置
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    /// 依次尝试的日期来源，前一个取不到日期时使用下一个；都取不到的文件不移动
+    #[serde(default = "default_date_sources")]
+    pub date_sources: Vec<DateSource>,
+    #[serde(default)]
+    pub collision: CollisionPolicy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub date: NaiveDate,
+}
+
+/// 不会移动的文件及原因
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// 移动计划，不会修改任何文件
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OrganizePlan {
+    pub moves: Vec<PlannedMove>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizeProgress {
+    pub done: usize,
+    pub total: usize,
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizeReport {
+    /// 操作日志中的 ID，用于撤销；没有改动任何文件或没能写入操作日志时为空
+    pub operation_id: Option<String>,
+    pub moved: usize,
+    /// 计划生成后发生变化、没能移动的文件
+    pub failed: Vec<SkippedFile>,
+    /// 文件已经移动、但没能写入操作日志时的原因，此时本次整理不能撤销
+    pub journal_error: Option<String>,
+}
+
+/// 生成移动计划，文件按名称排序
+pub fn plan(profile: &Profile, options: &OrganizeOptions) -> AppResult<OrganizePlan> {
+    profile.validate_path()?;
+    if options.date_sources.is_empty() {
+        return Err(AppError::configuration("至少需要一种日期来源"));
+    }
+    let source_dir = Path::new(&options.source_dir);
+    if !source_dir.is_dir() {
+        return Err(AppError::invalid_path("要整理的目录不存在"));
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(source_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    files.sort();
+
+    let mut plan = OrganizePlan::default();
+    let mut claimed = HashSet::new();
+    for path in files {
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_none_or(|name| name.starts_with('.'));
+        if !metadata.is_file() || hidden {
+            continue;
+        }
+
+        let skip = |reason: &str| SkippedFile {
+            path: path.clone(),
+            reason: reason.to_string(),
+        };
+        let date = match file_date(&path, &metadata, &options.date_sources) {
+            Ok(date) => date,
+            Err(reason) => {
+                plan.skipped.push(skip(&reason));
+                continue;
+            }
+        };
+
+        let folder = FolderManager::folder_path_for_date(profile, &date)?;
+        if folder == source_dir {
+            plan.skipped.push(skip("已在对应日期的文件夹中"));
+            continue;
+        }
+
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let taken = |candidate: &Path| candidate.exists() || claimed.contains(candidate);
+        match inbox::destination_with(&folder, &file_name, options.collision, &taken) {
+            Some(to) => {
+                claimed.insert(to.clone());
+                plan.moves.push(PlannedMove { from: path, to, date });
+            }
+            None => plan.skipped.push(skip("目标文件夹中已有同名文件")),
+        }
+    }
+
+    Ok(plan)
+}
+
+/// 按计划移动文件，每移动一个文件回调一次进度，完成后写入操作日志
+///
+/// 日期文件夹直接创建，不会应用脚手架或执行钩子。写入操作日志失败不影响已经完成的移动，
+/// 原因记在 [`OrganizeReport::journal_error`] 中。
+pub fn execute(
+    plan: &OrganizePlan,
+    source_dir: &Path,
+    journal: &Journal,
+    mut progress: impl FnMut(OrganizeProgress),
+) -> AppResult<OrganizeReport> {
+    let mut operation = Operation::new(OperationKind::Organize {
+        source_dir: source_dir.to_path_buf(),
+    });
+    let mut failed = Vec::new();
+    let mut moved = 0;
+    let total = plan.moves.len();
+
+    for (index, planned) in plan.moves.iter().enumerate() {
+        let result = if !planned.from.is_file() {
+            Err("文件已不存在".to_string())
+        } else if planned.to.exists() {
+            Err("目标位置已有文件".to_string())
+        } else {
+            let folder = planned.to.parent().unwrap_or(source_dir);
+            journal::create_dirs(folder, &mut operation.entries)
+                .map_err(|e| e.message)
+                .and_then(|_| {
+                    fs_util::move_file(&planned.from, &planned.to).map_err(|e| e.to_string())
+                })
+        };
+
+        match result {
+            Ok(()) => {
+                operation.entries.push(Entry::moved(&planned.from, &planned.to));
+                moved += 1;
+            }
+            Err(reason) => failed.push(SkippedFile {
+                path: planned.from.clone(),
+                reason,
+            }),
+        }
+        progress(OrganizeProgress {
+            done: index + 1,
+            total,
+            file: planned.from.clone(),
+        });
+    }
+
+    let mut operation_id = None;
+    let mut journal_error = None;
+    if !operation.entries.is_empty() {
+        match journal.append(&operation) {
+            Ok(()) => operation_id = Some(operation.id),
+            Err(e) => {
+                eprintln!("Warning: failed to record operation {}: {}", operation.id, e);
+                journal_error = Some(e.message);
+            }
+        }
+    }
+    println!("Organized {} files from {}", moved, source_dir.display());
+
+    Ok(OrganizeReport {
+        operation_id,
+        moved,
+        failed,
+        journal_error,
+    })
+}
+
+/// 从文件名中找出第一个有效日期
+pub fn date_from_file_name(name: &str) -> Option<NaiveDate> {
+    FILE_NAME_DATE.captures_iter(name).find_map(|captures| {
+        if captures[2] != captures[4] {
+            return None;
+        }
+        NaiveDate::from_ymd_opt(
+            captures[1].parse().ok()?,
+            captures[3].parse().ok()?,
+            captures[5].parse().ok()?,
+        )
+    })
+}
+
+/// 按来源顺序取第一个可用的日期，都取不到时返回各来源的原因
+fn file_date(path: &Path, metadata: &fs::Metadata, sources: &[DateSource]) -> Result<NaiveDate, String> {
+    let local_date = |time| DateTime::<Local>::from(time).date_naive();
+    let mut reasons = Vec::new();
+
+    for source in sources {
+        let date = match source {
+            DateSource::Metadata => media_date::capture_date(path).ok_or("没有拍摄日期"),
+            DateSource::FileName => path
+                .file_name()
+                .and_then(|name| date_from_file_name(&name.to_string_lossy()))
+                .ok_or("文件名中没有日期"),
+            DateSource::Modified => metadata.modified().map(local_date).map_err(|_| "无法读取修改时间"),
+            DateSource::Created => metadata.created().map(local_date).map_err(|_| "无法读取创建时间"),
+        };
+        match date {
+            Ok(date) => return Ok(date),
+            Err(reason) => reasons.push(reason),
+        }
+    }
+
+    Err(reasons.join("，"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::DateFormat;
+    use chrono::TimeZone;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_date_from_file_name() {
+        assert_eq!(date_from_file_name("IMG_20240315_101530.jpg"), Some(date(2024, 3, 15)));
+        assert_eq!(date_from_file_name("Screenshot 2024-03-15 at 10.22.33.png"), Some(date(2024, 3, 15)));
+        assert_eq!(date_from_file_name("backup_2023_12_31.tar"), Some(date(2023, 12, 31)));
+        assert_eq!(date_from_file_name("IMG-20240230-WA0001 20240301.jpg"), Some(date(2024, 3, 1)));
+        assert_eq!(date_from_file_name("2024-03_15.txt"), None);
+        assert_eq!(date_from_file_name("1710489600.log"), None);
+        assert_eq!(date_from_file_name("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_plan_execute_undo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("Downloads");
+        let base = temp_dir.path().join("Daily");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(base.join("2024-03-15")).unwrap();
+        fs::write(base.join("2024-03-15").join("a.jpg"), "old").unwrap();
+        for name in ["IMG_20240315_1.jpg", "a.jpg", "notes.txt", ".hidden"] {
+            fs::write(source.join(name), name).unwrap();
+        }
+        // a.jpg 按修改时间归入已有同名文件的 2024-03-15
+        let modified = Local.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(source.join("a.jpg"))
+            .unwrap()
+            .set_modified(modified.into())
+            .unwrap();
+
+        let profile = Profile {
+            folder_path: base.to_string_lossy().to_string(),
+            date_format: DateFormat::YYYYMMDD,
+            ..Profile::default()
+        };
+        let options = OrganizeOptions {
+            source_dir: source.to_string_lossy().to_string(),
+            profile_id: None,
+            date_sources: vec![DateSource::FileName],
+            collision: CollisionPolicy::Number,
+        };
+
+        let by_name = plan(&profile, &options).unwrap();
+        assert_eq!(by_name.moves.len(), 1);
+        assert_eq!(by_name.moves[0].to, base.join("2024-03-15").join("IMG_20240315_1.jpg"));
+        assert_eq!(by_name.skipped.len(), 2);
+        assert_eq!(by_name.skipped[0].reason, "文件名中没有日期");
+
+        let options = OrganizeOptions { date_sources: default_date_sources(), ..options };
+        let by_chain = plan(&profile, &options).unwrap();
+        let a = by_chain.moves.iter().find(|planned| planned.from.ends_with("a.jpg")).unwrap();
+        assert_eq!(a.to, base.join("2024-03-15").join("a (1).jpg"));
+        assert_eq!(by_chain.moves.len(), 3);
+        assert!(by_chain.moves.iter().all(|planned| planned.from.ends_with("notes.txt")
+            || planned.date == date(2024, 3, 15)));
+        assert!(source.join("a.jpg").exists(), "planning must not touch files");
+
+        let journal = Journal::at(temp_dir.path().join("data").join(journal::JOURNAL_FILE));
+        let mut events = Vec::new();
+        let report = execute(&by_chain, &source, &journal, |progress| events.push(progress)).unwrap();
+        assert_eq!(report.moved, 3);
+        assert!(report.failed.is_empty());
+        assert_eq!(events.last().map(|event| (event.done, event.total)), Some((3, 3)));
+        assert!(base.join("2024-03-15").join("a (1).jpg").is_file());
+        assert!(!source.join("notes.txt").exists());
+
+        let operation_id = report.operation_id.unwrap();
+        journal.undo(&operation_id).unwrap();
+        for name in ["IMG_20240315_1.jpg", "a.jpg", "notes.txt"] {
+            assert_eq!(fs::read_to_string(source.join(name)).unwrap(), name);
+        }
+        assert_eq!(fs::read_to_string(base.join("2024-03-15").join("a.jpg")).unwrap(), "old");
+        // 为 notes.txt 新建的今天的文件夹被删除，原有的 2024-03-15 保留
+        assert_eq!(fs::read_dir(&base).unwrap().count(), 1);
+        assert!(journal.undo(&operation_id).is_err());
+
+        // 操作日志写不进去时文件照常移动，报告中说明不能撤销
+        fs::write(temp_dir.path().join("blocked"), "").unwrap();
+        let blocked = Journal::at(temp_dir.path().join("blocked").join(journal::JOURNAL_FILE));
+        let replan = plan(&profile, &options).unwrap();
+        let report = execute(&replan, &source, &blocked, |_| {}).unwrap();
+        assert_eq!(report.moved, 3);
+        assert_eq!(report.operation_id, None);
+        assert!(report.journal_error.is_some());
+    }
+}
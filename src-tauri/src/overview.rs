@@ -0,0 +1,378 @@
+//! 月视图：一个月中每天是否有文件夹、里面有多少文件和多大空间，以及用户给这一天加的标签和备注
+//!
+//! 日期文件夹的清单和文件统计都按目录的修改时间缓存：目录没变且缓存未过期时直接复用，
+//! 只重新读取有变化的目录，大的基础路径也能很快返回。刚修改过的目录不缓存，见 [`MTIME_RESOLUTION`]。
+
+use crate::calendar::CalendarPolicy;
+use crate::error::{AppError, AppResult};
+use crate::folder::{CatalogEntry, FolderManager};
+use crate::fs_util;
+use crate::settings::{DateFormat, Profile};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// 日期文件夹中保存标签和备注的文件，不计入文件数和大小
+pub const META_FILE: &str = ".smartfolderzen.json";
+
+/// 超过这个时间的缓存即使目录没变也重新读取，以便发现原地修改的文件
+const CACHE_TTL: Duration = Duration::from_secs(300);
+/// 常见文件系统中最粗的修改时间精度（FAT/exFAT 为 2 秒）
+///
+/// 修改时间离读取时刻不到这么久的目录，之后在同一个时间刻度内再变化时修改时间可能不变，这样的目录不缓存。
+const MTIME_RESOLUTION: Duration = Duration::from_secs(2);
+/// 缓存的目录数超过上限时整体清空
+const CACHE_CAPACITY: usize = 100_000;
+
+static DIR_CACHE: Mutex<BTreeMap<PathBuf, DirStats>> = Mutex::new(BTreeMap::new());
+/// 按基础路径缓存的日期文件夹清单
+static CATALOG_CACHE: Mutex<BTreeMap<PathBuf, CachedCatalog>> = Mutex::new(BTreeMap::new());
+
+/// 用户给某一天加的标签和备注
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayMeta {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub note: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DayOverview {
+    pub date: NaiveDate,
+    pub exists: bool,
+    /// 当天文件夹的路径，不存在时为空
+    pub path: Option<PathBuf>,
+    /// 文件夹中（含子目录）的文件数
+    pub file_count: u64,
+    pub total_bytes: u64,
+    /// 文件夹中最近一次新增、删除或修改的时间
+    pub last_modified: Option<DateTime<Local>>,
+    pub is_workday: bool,
+    #[serde(flatten)]
+    pub meta: DayMeta,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthOverview {
+    pub profile_id: String,
+    pub year: i32,
+    pub month: u32,
+    pub days: Vec<DayOverview>,
+}
+
+/// 一个目录直接包含的文件的统计，以及它的子目录
+#[derive(Debug, Clone)]
+struct DirStats {
+    modified: Option<SystemTime>,
+    checked: Instant,
+    files: u64,
+    bytes: u64,
+    last_modified: Option<SystemTime>,
+    subdirs: Vec<PathBuf>,
+}
+
+/// 某个基础路径的 [`FolderManager::scan`] 结果，以及扫描时各上级目录的修改时间
+#[derive(Debug, Clone)]
+struct CachedCatalog {
+    levels: Vec<DateFormat>,
+    dirs: Vec<(PathBuf, Option<SystemTime>)>,
+    checked: Instant,
+    entries: Vec<CatalogEntry>,
+}
+
+/// 汇总一个月的概况，每天一项
+///
+/// 日期文件夹由 [`FolderManager::scan`] 找出，名称缺少年份（如 MMDD）的文件夹按修改时间归入对应的年份。
+pub fn month(profile: &Profile, calendar: &CalendarPolicy, year: i32, month: u32) -> AppResult<MonthOverview> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| AppError::configuration("月份无效"))?;
+
+    let mut folders: HashMap<NaiveDate, PathBuf> = HashMap::new();
+    for entry in catalog(profile)? {
+        if entry.date.year() == year && entry.date.month() == month {
+            folders.entry(entry.date).or_insert(entry.path);
+        }
+    }
+
+    let days = first
+        .iter_days()
+        .take_while(|date| date.month() == month)
+        .map(|date| {
+            let is_workday = calendar.is_workday(&date);
+            match folders.remove(&date) {
+                Some(path) => {
+                    let (file_count, total_bytes, last_modified) = folder_stats(&path);
+                    DayOverview {
+                        date,
+                        exists: true,
+                        file_count,
+                        total_bytes,
+                        last_modified: last_modified.map(DateTime::from),
+                        is_workday,
+                        meta: read_meta(&path),
+                        path: Some(path),
+                    }
+                }
+                None => DayOverview {
+                    date,
+                    exists: false,
+                    path: None,
+                    file_count: 0,
+                    total_bytes: 0,
+                    last_modified: None,
+                    is_workday,
+                    meta: DayMeta::default(),
+                },
+            }
+        })
+        .collect();
+
+    Ok(MonthOverview {
+        profile_id: profile.id.clone(),
+        year,
+        month,
+        days,
+    })
+}
+
+/// 指定日期已存在的文件夹
+pub fn day_folder(profile: &Profile, date: &NaiveDate) -> AppResult<PathBuf> {
+    catalog(profile)?
+        .into_iter()
+        .find(|entry| entry.date == *date)
+        .map(|entry| entry.path)
+        .ok_or_else(|| AppError::invalid_path("当天的文件夹不存在"))
+}
+
+/// 基础路径中的日期文件夹；基础路径和各层上级目录都没有变化时复用上次的扫描结果
+fn catalog(profile: &Profile) -> AppResult<Vec<CatalogEntry>> {
+    let base = PathBuf::from(&profile.folder_path);
+    let levels = profile.naming_levels().to_vec();
+    // 先记下目录状态再扫描，扫描期间发生的变化会让下一次重新扫描
+    let dirs = parent_dirs(&base, levels.len());
+
+    if let Ok(cache) = CATALOG_CACHE.lock() {
+        let cached = cache.get(&base).filter(|cached| {
+            cached.levels == levels && cached.dirs == dirs && cached.checked.elapsed() < CACHE_TTL
+        });
+        if let Some(cached) = cached {
+            return Ok(cached.entries.clone());
+        }
+    }
+
+    let entries = FolderManager::scan(profile, None)?;
+    if !dirs.iter().all(|(_, modified)| settled(*modified)) {
+        return Ok(entries);
+    }
+    if let Ok(mut cache) = CATALOG_CACHE.lock() {
+        cache.insert(base, CachedCatalog {
+            levels,
+            dirs,
+            checked: Instant::now(),
+            entries: entries.clone(),
+        });
+    }
+    Ok(entries)
+}
+
+/// 日期文件夹之上的各层目录（含基础路径）及其修改时间；日期文件夹增删改名都会改变其中某个目录的修改时间
+fn parent_dirs(base: &Path, depth: usize) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut dirs: Vec<_> = (0..depth)
+        .flat_map(|level| FolderManager::dirs_at_depth(base, level))
+        .map(|dir| {
+            let modified = fs::metadata(&dir).and_then(|metadata| metadata.modified()).ok();
+            (dir, modified)
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// 读取文件夹的标签和备注，没有或无法解析时为空
+pub fn read_meta(folder: &Path) -> DayMeta {
+    fs::read(folder.join(META_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 保存文件夹的标签和备注；都为空时删除记录文件
+pub fn write_meta(folder: &Path, meta: &DayMeta) -> AppResult<()> {
+    if !folder.is_dir() {
+        return Err(AppError::invalid_path("当天的文件夹不存在"));
+    }
+    let meta = DayMeta {
+        tags: meta
+            .tags
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect(),
+        note: meta.note.trim_end().to_string(),
+    };
+
+    let path = folder.join(META_FILE);
+    if meta == DayMeta::default() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+    let content = serde_json::to_vec_pretty(&meta)
+        .map_err(|e| AppError::file_system(&format!("无法保存标签和备注: {}", e)))?;
+    fs_util::write_atomic(&path, &content)?;
+    Ok(())
+}
+
+/// 文件夹（含子目录）的文件数、总大小和最近修改时间
+fn folder_stats(folder: &Path) -> (u64, u64, Option<SystemTime>) {
+    let (mut files, mut bytes, mut last_modified) = (0, 0, None);
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Some(stats) = dir_stats(&dir) else {
+            continue;
+        };
+        files += stats.files;
+        bytes += stats.bytes;
+        last_modified = last_modified.max(stats.last_modified);
+        pending.extend(stats.subdirs);
+    }
+    (files, bytes, last_modified)
+}
+
+/// 目录直接包含的文件的统计；目录的修改时间没变且缓存未过期时不重新读取
+///
+/// 缓存只在查找和写入时短暂加锁，读取目录期间不持有锁，多个请求可以同时统计。
+fn dir_stats(dir: &Path) -> Option<DirStats> {
+    let modified = fs::metadata(dir).and_then(|metadata| metadata.modified()).ok();
+    let cached = DIR_CACHE.lock().ok().and_then(|cache| {
+        cache
+            .get(dir)
+            .filter(|stats| stats.modified == modified && stats.checked.elapsed() < CACHE_TTL)
+            .cloned()
+    });
+    if cached.is_some() {
+        return cached;
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        if let Ok(mut cache) = DIR_CACHE.lock() {
+            cache.remove(dir);
+        }
+        return None;
+    };
+    let mut stats = DirStats {
+        modified,
+        checked: Instant::now(),
+        files: 0,
+        bytes: 0,
+        last_modified: modified,
+        subdirs: Vec::new(),
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            stats.subdirs.push(entry.path());
+        } else if entry.file_name() != META_FILE {
+            stats.files += 1;
+            stats.bytes += metadata.len();
+            stats.last_modified = stats.last_modified.max(metadata.modified().ok());
+        }
+    }
+
+    if let Ok(mut cache) = DIR_CACHE.lock() {
+        if !settled(modified) {
+            cache.remove(dir);
+        } else {
+            if cache.len() > CACHE_CAPACITY {
+                cache.clear();
+            }
+            cache.insert(dir.to_path_buf(), stats.clone());
+        }
+    }
+    Some(stats)
+}
+
+/// 修改时间已经早于 [`MTIME_RESOLUTION`]，之后的任何变化都会让修改时间不同
+fn settled(modified: Option<SystemTime>) -> bool {
+    modified.is_some_and(|modified| {
+        SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age >= MTIME_RESOLUTION)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_month_overview() {
+        let temp_dir = tempdir().unwrap();
+        let profile = Profile {
+            folder_path: temp_dir.path().to_string_lossy().to_string(),
+            date_format: DateFormat::YYYYMMDD,
+            ..Profile::default()
+        };
+        let day = temp_dir.path().join("2024-03-15");
+        fs::create_dir_all(day.join("sub")).unwrap();
+        fs::write(day.join("a.txt"), "12345").unwrap();
+        fs::write(day.join("sub").join("b.txt"), "123").unwrap();
+        fs::create_dir(temp_dir.path().join("2024-04-01")).unwrap();
+
+        let meta = DayMeta {
+            tags: vec![" 出差 ".to_string(), "".to_string()],
+            note: "客户拜访\n".to_string(),
+        };
+        write_meta(&day, &meta).unwrap();
+        assert!(write_meta(&temp_dir.path().join("2024-03-16"), &meta).is_err());
+
+        let overview = month(&profile, &CalendarPolicy::default(), 2024, 3).unwrap();
+        assert_eq!(overview.days.len(), 31);
+        let existing: Vec<_> = overview.days.iter().filter(|day| day.exists).collect();
+        assert_eq!(existing.len(), 1);
+        assert_eq!(existing[0].date, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+        assert_eq!((existing[0].file_count, existing[0].total_bytes), (2, 8));
+        assert!(existing[0].last_modified.is_some());
+        assert_eq!(existing[0].meta.tags, ["出差"]);
+        assert_eq!(existing[0].meta.note, "客户拜访");
+
+        // 新增和删除文件后，只有发生变化的目录会重新统计
+        fs::write(day.join("sub").join("c.txt"), "1").unwrap();
+        fs::remove_file(day.join("a.txt")).unwrap();
+        assert_eq!(folder_stats(&day).0, 2);
+        assert_eq!(folder_stats(&day).1, 4);
+
+        // 新建的日期文件夹让缓存的清单失效
+        fs::create_dir(temp_dir.path().join("2024-03-16")).unwrap();
+        let overview = month(&profile, &CalendarPolicy::default(), 2024, 3).unwrap();
+        assert_eq!(overview.days.iter().filter(|day| day.exists).count(), 2);
+        let date = |day: u32| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        assert_eq!(day_folder(&profile, &date(16)).unwrap(), temp_dir.path().join("2024-03-16"));
+        assert!(day_folder(&profile, &date(17)).is_err());
+
+        // 刚修改过的目录不缓存；修改时间足够早之后才缓存，此后目录的变化一定会改变修改时间
+        let cached = |dir: &Path| DIR_CACHE.lock().unwrap().contains_key(dir);
+        assert!(!cached(&day));
+        let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::open(&day).unwrap().set_modified(hour_ago).unwrap();
+        folder_stats(&day);
+        assert!(cached(&day));
+        fs::write(day.join("d.txt"), "12").unwrap();
+        assert_eq!(folder_stats(&day), (3, 6, folder_stats(&day).2));
+        assert!(!cached(&day));
+
+        // 清空标签和备注时删除记录文件
+        write_meta(&day, &DayMeta::default()).unwrap();
+        assert!(!day.join(META_FILE).exists());
+        assert!(month(&profile, &CalendarPolicy::default(), 2024, 13).is_err());
+    }
+}
@@ -0,0 +1,300 @@
+//! 命名格式改变后，把按旧格式命名的日期文件夹重命名为新格式
+//!
+//! 先用 [`plan`] 按旧格式解析已有文件夹名并生成重命名计划供预览，确认后 [`execute`] 执行并写入操作日志，可整体撤销。
+
+use crate::error::{AppError, AppResult};
+use crate::folder::{FolderKind, FolderManager};
+use crate::fs_util;
+use crate::journal::{self, Entry, Journal, Operation, OperationKind};
+use crate::settings::{DateFormat, Profile};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 保存设置后某个配置的命名层级发生变化时发出，负载为 [`NamingChanged`]
+pub const NAMING_CHANGED_EVENT: &str = "naming-changed";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NamingChanged {
+    pub profile_id: String,
+    /// 变化前的命名层级，可直接用作 [`RenameOptions::from_levels`]
+    pub from_levels: Vec<DateFormat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenameOptions {
+    /// 要迁移的配置，为空时使用默认配置；文件夹会被重命名为它当前的命名格式
+    #[serde(default)]
+    pub profile_id: Option<String>,
+    /// 旧的命名层级，只有一层时就是旧的 `date_format`
+    pub from_levels: Vec<DateFormat>,
+    /// 旧格式缺少年份（如 MMDD）时使用的年份；为空时按文件夹的修改时间推断
+    #[serde(default)]
+    pub year: Option<i32>,
+    /// 是否也执行年份靠推断得出的重命名
+    #[serde(default)]
+    pub include_ambiguous: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub date: NaiveDate,
+    /// 日期不能从名称中唯一确定时的说明，如按修改时间推断的年份
+    pub ambiguity: Option<String>,
+}
+
+/// 不会重命名的文件夹及原因
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFolder {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// 重命名计划，不会修改任何文件
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RenamePlan {
+    pub renames: Vec<PlannedRename>,
+    pub skipped: Vec<SkippedFolder>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenameReport {
+    /// 操作日志中的 ID，用于撤销；没有改动任何文件夹或没能写入操作日志时为空
+    pub operation_id: Option<String>,
+    pub renamed: usize,
+    /// 计划生成后发生变化、没能重命名的文件夹
+    pub failed: Vec<SkippedFolder>,
+    /// 文件夹已经重命名、但没能写入操作日志时的原因，此时本次重命名不能撤销
+    pub journal_error: Option<String>,
+}
+
+/// 生成重命名计划，按日期排序
+///
+/// 只查看与旧命名层级同样深度的目录；名称按旧格式解析不出日期的目录被忽略，已符合新格式的目录不会出现在计划中。
+pub fn plan(profile: &Profile, options: &RenameOptions) -> AppResult<RenamePlan> {
+    profile.validate_path()?;
+    let old = Profile {
+        folder_levels: options.from_levels.clone(),
+        ..profile.clone()
+    };
+    if options.from_levels.is_empty() {
+        return Err(AppError::configuration("旧的命名格式不能为空"));
+    }
+    if old.naming_levels() == profile.naming_levels() {
+        return Err(AppError::configuration("新旧命名格式相同，无需重命名"));
+    }
+    for format in old.naming_levels() {
+        format.validate()?;
+    }
+
+    // 指定了年份时以当年最后一天为参考，所有月日都落在这一年
+    let reference = options.year.and_then(|year| NaiveDate::from_ymd_opt(year, 12, 31));
+    let base = PathBuf::from(&profile.folder_path);
+    let mut found = Vec::new();
+    for entry in FolderManager::scan(&old, reference)? {
+        let Ok(relative) = entry.path.strip_prefix(&base) else {
+            continue;
+        };
+        if profile.date_from_relative_path(relative).is_some() {
+            continue;
+        }
+
+        let ambiguity = (entry.kind == FolderKind::InferredYear && reference.is_none())
+            .then(|| format!("名称中没有年份，按修改时间推断为 {} 年", entry.date.year()));
+        found.push((entry.date, entry.path, ambiguity));
+    }
+
+    // 多个旧文件夹对应同一天时都不重命名，交给用户处理
+    let mut seen = HashSet::new();
+    let duplicates: HashSet<NaiveDate> = found
+        .iter()
+        .filter(|(date, ..)| !seen.insert(*date))
+        .map(|(date, ..)| *date)
+        .collect();
+
+    let mut plan = RenamePlan::default();
+    for (date, from, ambiguity) in found {
+        let skip = |reason: &str| SkippedFolder {
+            path: from.clone(),
+            reason: reason.to_string(),
+        };
+        let to = FolderManager::folder_path_for_date(profile, &date)?;
+        if duplicates.contains(&date) {
+            plan.skipped.push(skip("有多个文件夹对应同一天"));
+        } else if to.exists() {
+            plan.skipped.push(skip("新名称的文件夹已存在"));
+        } else if to.starts_with(&from) {
+            plan.skipped.push(skip("新位置位于原文件夹之内"));
+        } else {
+            plan.renames.push(PlannedRename { from, to, date, ambiguity });
+        }
+    }
+
+    Ok(plan)
+}
+
+/// 按计划重命名文件夹，完成后写入操作日志；`include_ambiguous` 为假时跳过日期靠推断得出的项
+///
+/// 目录移走后变空的旧上级目录（如旧格式的年、月文件夹）会被删除。写入操作日志失败不影响已经完成的重命名，
+/// 原因记在 [`RenameReport::journal_error`] 中。
+pub fn execute(
+    plan: &RenamePlan,
+    profile: &Profile,
+    include_ambiguous: bool,
+    journal: &Journal,
+) -> AppResult<RenameReport> {
+    let base = PathBuf::from(&profile.folder_path);
+    let mut operation = Operation::new(OperationKind::RenameFolders {
+        profile_id: profile.id.clone(),
+    });
+    let mut failed = Vec::new();
+    let mut renamed = 0;
+
+    for planned in &plan.renames {
+        if planned.ambiguity.is_some() && !include_ambiguous {
+            continue;
+        }
+
+        let result = if !planned.from.is_dir() {
+            Err("文件夹已不存在".to_string())
+        } else if planned.to.exists() {
+            Err("新名称的文件夹已存在".to_string())
+        } else {
+            let parent = planned.to.parent().unwrap_or(&base);
+            journal::create_dirs(parent, &mut operation.entries)
+                .map_err(|e| e.message)
+                .and_then(|_| fs_util::move_dir(&planned.from, &planned.to).map_err(|e| e.to_string()))
+        };
+
+        match result {
+            Ok(()) => {
+                operation.entries.push(Entry::moved(&planned.from, &planned.to));
+                remove_empty_parents(&planned.from, &base);
+                renamed += 1;
+            }
+            Err(reason) => failed.push(SkippedFolder {
+                path: planned.from.clone(),
+                reason,
+            }),
+        }
+    }
+
+    let mut operation_id = None;
+    let mut journal_error = None;
+    if !operation.entries.is_empty() {
+        match journal.append(&operation) {
+            Ok(()) => operation_id = Some(operation.id),
+            Err(e) => {
+                eprintln!("Warning: failed to record operation {}: {}", operation.id, e);
+                journal_error = Some(e.message);
+            }
+        }
+    }
+    println!("Renamed {} folders in {}", renamed, base.display());
+
+    Ok(RenameReport {
+        operation_id,
+        renamed,
+        failed,
+        journal_error,
+    })
+}
+
+/// 删除 `path` 在 `base` 之下变空的上级目录
+fn remove_empty_parents(path: &Path, base: &Path) {
+    for parent in path.ancestors().skip(1) {
+        if parent == base || !parent.starts_with(base) || fs::remove_dir(parent).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rename_mmdd_to_nested() {
+        let temp_dir = tempdir().unwrap();
+        let base = temp_dir.path();
+        let profile = Profile {
+            folder_path: base.to_string_lossy().to_string(),
+            folder_levels: vec![DateFormat::Custom("%Y".to_string()), DateFormat::YYYYMMDD],
+            ..Profile::default()
+        };
+        for name in ["0315", "0316", "1301", "notes", "2024/2024-03-17"] {
+            fs::create_dir_all(base.join(name)).unwrap();
+        }
+        fs::write(base.join("0315").join("a.txt"), "a").unwrap();
+        // 新位置已被占用
+        fs::create_dir_all(base.join("2023/2023-03-16")).unwrap();
+
+        let mut options = RenameOptions {
+            profile_id: None,
+            from_levels: vec![DateFormat::MMDD],
+            year: Some(2023),
+            include_ambiguous: false,
+        };
+        let plan = plan(&profile, &options).unwrap();
+        assert_eq!(plan.renames.len(), 1);
+        assert_eq!(plan.renames[0].to, base.join("2023/2023-03-15"));
+        assert_eq!(plan.renames[0].ambiguity, None);
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.skipped[0].path, base.join("0316"));
+
+        // 没有指定年份时按修改时间推断，默认不执行
+        options.year = None;
+        let inferred = super::plan(&profile, &options).unwrap();
+        assert!(inferred.renames.iter().all(|rename| rename.ambiguity.is_some()));
+        let journal = Journal::at(base.join("journal.jsonl"));
+        assert_eq!(execute(&inferred, &profile, false, &journal).unwrap().renamed, 0);
+
+        let report = execute(&plan, &profile, false, &journal).unwrap();
+        assert_eq!(report.renamed, 1);
+        assert!(!base.join("0315").exists());
+        assert_eq!(fs::read_to_string(base.join("2023/2023-03-15/a.txt")).unwrap(), "a");
+
+        journal.undo(&report.operation_id.unwrap()).unwrap();
+        assert!(base.join("0315").join("a.txt").is_file());
+        assert!(!base.join("2023/2023-03-15").exists());
+        assert!(base.join("2023/2023-03-16").exists());
+    }
+
+    #[test]
+    fn test_rename_nested_to_flat() {
+        let temp_dir = tempdir().unwrap();
+        let base = temp_dir.path();
+        let profile = Profile {
+            folder_path: base.to_string_lossy().to_string(),
+            date_format: DateFormat::YYYYMMDD,
+            ..Profile::default()
+        };
+        for name in ["2024/03/15", "2024/03/16", "2024/04/01", "2024-03-20"] {
+            fs::create_dir_all(base.join(name)).unwrap();
+        }
+
+        let options = RenameOptions {
+            profile_id: None,
+            from_levels: ["%Y", "%m", "%d"].map(|pattern| DateFormat::Custom(pattern.to_string())).to_vec(),
+            year: None,
+            include_ambiguous: false,
+        };
+        let plan = plan(&profile, &options).unwrap();
+        let targets: Vec<_> = plan.renames.iter().map(|rename| rename.to.clone()).collect();
+        assert_eq!(targets, ["2024-03-15", "2024-03-16", "2024-04-01"].map(|name| base.join(name)));
+
+        // 操作日志写不进去时照常重命名，报告中说明不能撤销
+        fs::write(base.join("blocked"), "").unwrap();
+        let report = execute(&plan, &profile, false, &Journal::at(base.join("blocked").join("journal.jsonl"))).unwrap();
+        assert_eq!(report.renamed, 3);
+        assert_eq!(report.operation_id, None);
+        assert!(report.journal_error.is_some());
+        assert!(!base.join("2024").exists());
+        assert_eq!(FolderManager::scan(&profile, None).unwrap().len(), 4);
+    }
+}
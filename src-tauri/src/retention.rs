@@ -0,0 +1,566 @@
+//! 归档旧文件夹：找出早于保留天数的日期文件夹，移到归档目录、压缩，或把空文件夹移入回收站
+//!
+//! 每次归档都追加记录在归档目录的 `manifest.jsonl` 中。
+
+use crate::error::{AppError, AppResult};
+use crate::folder::{FolderKind, FolderManager};
+use crate::fs_util;
+use crate::journal::{self, Entry, Journal, Operation, OperationKind};
+use crate::scheduler::{self, SystemClock, Trigger};
+use crate::settings::AppSettings;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager, Runtime};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 归档清单文件名，位于归档目录
+pub const MANIFEST_FILE: &str = "manifest.jsonl";
+
+/// 定时归档完成，负载为 [`RetentionReport`]
+pub const RETENTION_FINISHED_EVENT: &str = "retention-finished";
+
+/// 旧文件夹归档设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionSettings {
+    /// 每天在 `run_at` 自动执行；关闭时仍可手动执行
+    pub enabled: bool,
+    pub run_at: NaiveTime,
+    /// 日期早于这么多天之前的文件夹会被归档
+    pub older_than_days: u32,
+    /// 归档目录，各配置的文件夹放在以配置 ID 命名的子目录中
+    pub archive_root: String,
+    pub mode: ArchiveMode,
+    /// 不含任何文件的文件夹直接移入回收站，不归档
+    pub trash_empty: bool,
+    /// 只处理这些配置，为空时处理全部配置
+    pub profile_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveMode {
+    /// 保持原有层级移到归档目录
+    #[default]
+    Move,
+    /// 每个文件夹压缩成一个 zip，压缩后删除原文件夹，不能撤销
+    ZipPerFolder,
+    /// 同一个月的文件夹压缩进同一个 zip，如 `2024-03.zip`，同样不能撤销
+    ZipPerMonth,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            run_at: NaiveTime::from_hms_opt(3, 0, 0).expect("static time is valid"),
+            older_than_days: 90,
+            archive_root: String::new(),
+            mode: ArchiveMode::default(),
+            trash_empty: false,
+            profile_ids: Vec::new(),
+        }
+    }
+}
+
+impl RetentionSettings {
+    pub fn validate(&self) -> AppResult<()> {
+        if self.older_than_days == 0 {
+            return Err(AppError::configuration("保留天数必须大于 0"));
+        }
+        if self.enabled && self.archive_root.trim().is_empty() {
+            return Err(AppError::configuration("请设置归档目录"));
+        }
+        Ok(())
+    }
+}
+
+/// 对一个文件夹的处理
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RetentionAction {
+    Move { to: PathBuf },
+    /// 压缩进 `archive`，文件夹在压缩包中的路径为 `entry`
+    ///
+    /// 压缩成功后原文件夹被直接删除，这一步不记入操作日志，只能从压缩包中手动恢复。
+    Zip { archive: PathBuf, entry: String },
+    Trash,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionItem {
+    pub profile_id: String,
+    pub date: NaiveDate,
+    pub path: PathBuf,
+    /// 相对于配置基础路径的层级，归档后逐级删除变空的上级目录
+    pub relative: PathBuf,
+    /// 名称中没有年份（如 MMDD）时为 `InferredYear`，`date` 的年份按修改时间推断
+    pub kind: FolderKind,
+    pub action: RetentionAction,
+}
+
+/// 归档计划，不会修改任何文件
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionPlan {
+    /// 早于这一天的文件夹会被归档
+    pub cutoff: NaiveDate,
+    pub items: Vec<RetentionItem>,
+}
+
+/// 清单中的一条记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestRecord {
+    pub at: DateTime<Local>,
+    pub profile_id: String,
+    pub date: NaiveDate,
+    pub source: PathBuf,
+    pub action: RetentionAction,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionFailure {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionReport {
+    pub archived: Vec<ManifestRecord>,
+    pub failed: Vec<RetentionFailure>,
+}
+
+/// 生成归档计划
+pub fn plan(settings: &AppSettings, today: NaiveDate) -> AppResult<RetentionPlan> {
+    let retention = &settings.retention;
+    retention.validate()?;
+    if retention.archive_root.trim().is_empty() {
+        return Err(AppError::configuration("请设置归档目录"));
+    }
+    let archive_root = PathBuf::from(&retention.archive_root);
+
+    let cutoff = today - Duration::days(retention.older_than_days.into());
+    let profiles = if retention.profile_ids.is_empty() {
+        settings.profiles.iter().collect()
+    } else {
+        retention
+            .profile_ids
+            .iter()
+            .map(|id| settings.profile(Some(id)))
+            .collect::<AppResult<Vec<_>>>()?
+    };
+
+    let mut items = Vec::new();
+    for profile in profiles {
+        let base = PathBuf::from(&profile.folder_path);
+        let root = archive_root.join(&profile.id);
+
+        // 推断的年份不早于实际年份（文件夹只会在创建之后被修改），所以不会提前归档较新的文件夹
+        for entry in FolderManager::scan(profile, None)? {
+            let (date, path) = (entry.date, entry.path);
+            if date >= cutoff {
+                break;
+            }
+            let Ok(relative) = path.strip_prefix(&base).map(Path::to_path_buf) else {
+                continue;
+            };
+
+            let action = if retention.trash_empty && tally(&path)?.0 == 0 {
+                RetentionAction::Trash
+            } else {
+                match retention.mode {
+                    ArchiveMode::Move => RetentionAction::Move { to: root.join(&relative) },
+                    ArchiveMode::ZipPerFolder => {
+                        let mut archive = root.join(&relative).into_os_string();
+                        archive.push(".zip");
+                        RetentionAction::Zip {
+                            archive: archive.into(),
+                            entry: relative.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                        }
+                    }
+                    ArchiveMode::ZipPerMonth => RetentionAction::Zip {
+                        archive: root.join(format!("{}.zip", date.format("%Y-%m"))),
+                        entry: zip_name(&relative),
+                    },
+                }
+            };
+
+            items.push(RetentionItem {
+                profile_id: profile.id.clone(),
+                date,
+                path,
+                relative,
+                kind: entry.kind,
+                action,
+            });
+        }
+    }
+
+    Ok(RetentionPlan { cutoff, items })
+}
+
+/// 按计划归档，每处理完一个文件夹写入一条清单记录
+///
+/// 移动的文件夹记入操作日志，可以撤销；压缩和移入回收站的文件夹只记录在清单中，
+/// 其中压缩后被删除的原文件夹不能撤销，只能从压缩包中恢复。
+pub fn execute(plan: &RetentionPlan, archive_root: &Path, journal: &Journal) -> AppResult<RetentionReport> {
+    let mut report = RetentionReport {
+        archived: Vec::new(),
+        failed: Vec::new(),
+    };
+    let mut operation = Operation::new(OperationKind::Retention);
+
+    for item in &plan.items {
+        let result = tally(&item.path).and_then(|(files, bytes)| {
+            archive(item, &mut operation.entries)?;
+            Ok(ManifestRecord {
+                at: Local::now(),
+                profile_id: item.profile_id.clone(),
+                date: item.date,
+                source: item.path.clone(),
+                action: item.action.clone(),
+                files,
+                bytes,
+            })
+        });
+
+        match result {
+            Ok(record) => {
+                remove_empty_parents(&item.path, &item.relative);
+                if let Err(e) = append_manifest(archive_root, &record) {
+                    eprintln!("Warning: failed to write retention manifest: {}", e);
+                }
+                report.archived.push(record);
+            }
+            Err(e) => report.failed.push(RetentionFailure {
+                path: item.path.clone(),
+                reason: e.message,
+            }),
+        }
+    }
+
+    journal.record(&operation);
+    println!(
+        "Retention: archived {} folders, {} failed",
+        report.archived.len(),
+        report.failed.len()
+    );
+    Ok(report)
+}
+
+/// 生成计划并执行
+pub fn run(settings: &AppSettings, today: NaiveDate, journal: &Journal) -> AppResult<RetentionReport> {
+    let plan = plan(settings, today)?;
+    execute(&plan, Path::new(&settings.retention.archive_root), journal)
+}
+
+/// 在后台线程中按设置的时间每天执行一次
+pub fn start<R: Runtime>(app: tauri::AppHandle<R>) {
+    std::thread::spawn(move || {
+        let settings = || {
+            let state = app.state::<Mutex<AppSettings>>();
+            let settings = state.lock().ok()?;
+            Some(settings.clone())
+        };
+
+        scheduler::run(
+            &SystemClock,
+            || settings().filter(|settings| settings.retention.enabled).map(|settings| settings.retention.run_at),
+            |trigger| {
+                if trigger != Trigger::ScheduledTime {
+                    return ControlFlow::Continue(());
+                }
+                let Some(settings) = settings().filter(|settings| settings.retention.enabled) else {
+                    return ControlFlow::Continue(());
+                };
+                match Journal::global().and_then(|journal| run(&settings, Local::now().date_naive(), &journal)) {
+                    Ok(report) => {
+                        let _ = app.emit(RETENTION_FINISHED_EVENT, report);
+                    }
+                    Err(e) => eprintln!("Retention: scheduled run failed: {}", e),
+                }
+                ControlFlow::Continue(())
+            },
+        );
+    });
+}
+
+fn archive(item: &RetentionItem, entries: &mut Vec<Entry>) -> AppResult<()> {
+    match &item.action {
+        RetentionAction::Trash => trash::delete(&item.path)
+            .map_err(|e| AppError::file_system(&format!("移入回收站失败: {}", e))),
+        RetentionAction::Move { to } => {
+            if to.exists() {
+                return Err(AppError::file_system("归档目录中已有同名文件夹"));
+            }
+            journal::create_dirs(to.parent().unwrap_or(to), entries)?;
+            fs_util::move_dir(&item.path, to)
+                .map_err(|e| AppError::file_system(&format!("移动文件夹失败: {}", e)))?;
+            entries.push(Entry::moved(&item.path, to));
+            Ok(())
+        }
+        RetentionAction::Zip { archive, entry } => {
+            add_to_zip(archive, entry, &item.path)?;
+            // 删除不记入操作日志，压缩包就是唯一的副本
+            fs::remove_dir_all(&item.path)
+                .map_err(|e| AppError::file_system(&format!("压缩后删除文件夹失败: {}", e)))
+        }
+    }
+}
+
+/// 把文件夹压缩进 zip，已有的压缩包会被追加
+///
+/// 先写入临时文件，完成后再替换，中途失败不会损坏已有的压缩包。
+fn add_to_zip(archive: &Path, entry: &str, folder: &Path) -> AppResult<()> {
+    let zip_error = |e: zip::result::ZipError| AppError::file_system(&format!("压缩失败: {}", e));
+    if let Some(dir) = archive.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut temp_name = archive.as_os_str().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+
+    let result = (|| {
+        let mut writer = if archive.exists() {
+            let existing = ZipArchive::new(File::open(archive)?).map_err(zip_error)?;
+            let prefix = format!("{}/", entry);
+            if existing.file_names().any(|name| name.starts_with(&prefix)) {
+                return Err(AppError::file_system(&format!("压缩包中已有 {}", entry)));
+            }
+            fs::copy(archive, &temp_path)?;
+            let file = OpenOptions::new().read(true).write(true).open(&temp_path)?;
+            ZipWriter::new_append(file).map_err(zip_error)?
+        } else {
+            ZipWriter::new(File::create(&temp_path)?)
+        };
+
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.add_directory(format!("{}/", entry), options).map_err(zip_error)?;
+        for path in walk(folder)? {
+            let name = format!("{}/{}", entry, zip_name(path.strip_prefix(folder).unwrap_or(&path)));
+            if path.is_dir() {
+                writer.add_directory(format!("{}/", name), options).map_err(zip_error)?;
+            } else {
+                writer.start_file(name, options).map_err(zip_error)?;
+                io::copy(&mut File::open(&path)?, &mut writer)?;
+            }
+        }
+        writer.finish().map_err(zip_error)?.sync_all()?;
+        fs::rename(&temp_path, archive)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// 压缩包中的路径统一使用 `/` 分隔
+fn zip_name(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 目录下的全部文件和子目录，上级目录在前
+fn walk(dir: &Path) -> AppResult<Vec<PathBuf>> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+
+    let mut paths = Vec::new();
+    for path in entries {
+        let is_dir = fs::symlink_metadata(&path)?.is_dir();
+        paths.push(path.clone());
+        if is_dir {
+            paths.extend(walk(&path)?);
+        }
+    }
+    Ok(paths)
+}
+
+/// 文件数和总字节数
+fn tally(dir: &Path) -> AppResult<(u64, u64)> {
+    let mut files = 0;
+    let mut bytes = 0;
+    for path in walk(dir)? {
+        let metadata = fs::symlink_metadata(&path)?;
+        if !metadata.is_dir() {
+            files += 1;
+            bytes += metadata.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// 逐级删除变空的上级目录（如归档了当月最后一天后的月份目录），不会越过配置的基础路径
+fn remove_empty_parents(path: &Path, relative: &Path) {
+    let depth = relative.components().count();
+    for parent in path.ancestors().skip(1).take(depth.saturating_sub(1)) {
+        if fs::remove_dir(parent).is_err() {
+            break;
+        }
+    }
+}
+
+fn append_manifest(archive_root: &Path, record: &ManifestRecord) -> AppResult<()> {
+    fs::create_dir_all(archive_root)?;
+    let mut line = serde_json::to_string(record)
+        .map_err(|e| AppError::file_system(&format!("无法写入归档清单: {}", e)))?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(archive_root.join(MANIFEST_FILE))?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{DateFormat, Profile};
+    use chrono::Datelike;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn settings(base: &Path, archive_root: &Path, mode: ArchiveMode) -> AppSettings {
+        AppSettings {
+            profiles: vec![Profile {
+                id: "work".to_string(),
+                folder_path: base.to_string_lossy().to_string(),
+                folder_levels: vec![
+                    DateFormat::Custom("%Y-%m".to_string()),
+                    DateFormat::Custom("%d".to_string()),
+                ],
+                ..Profile::default()
+            }],
+            retention: RetentionSettings {
+                older_than_days: 30,
+                archive_root: archive_root.to_string_lossy().to_string(),
+                mode,
+                trash_empty: true,
+                ..RetentionSettings::default()
+            },
+            ..AppSettings::default()
+        }
+    }
+
+    fn create(base: &Path, relative: &str, file: Option<&str>) {
+        let dir = base.join(relative);
+        fs::create_dir_all(&dir).unwrap();
+        if let Some(file) = file {
+            fs::write(dir.join(file), file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_plan() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path().join("daily");
+        let archive_root = temp_dir.path().join("archive");
+        create(&base, "2024-01/15", Some("a.txt"));
+        create(&base, "2024-01/16", None);
+        create(&base, "2024-03/01", Some("b.txt"));
+        create(&base, "2024-01/notes", Some("c.txt"));
+
+        let today = date(2024, 3, 15);
+        let plan = plan(&settings(&base, &archive_root, ArchiveMode::ZipPerMonth), today).unwrap();
+        assert_eq!(plan.cutoff, date(2024, 2, 14));
+        let actions: Vec<_> = plan.items.iter().map(|item| (item.date, item.action.clone())).collect();
+        assert_eq!(
+            actions,
+            vec![
+                (
+                    date(2024, 1, 15),
+                    RetentionAction::Zip {
+                        archive: archive_root.join("work").join("2024-01.zip"),
+                        entry: "2024-01/15".to_string(),
+                    }
+                ),
+                (date(2024, 1, 16), RetentionAction::Trash),
+            ]
+        );
+
+        // 未设置归档目录时不能归档
+        let mut no_root = settings(&base, Path::new(""), ArchiveMode::Move);
+        assert!(super::plan(&no_root, today).is_err());
+        assert!(no_root.retention.validate().is_ok());
+        no_root.retention.enabled = true;
+        assert!(no_root.retention.validate().is_err());
+    }
+
+    #[test]
+    fn test_move_and_zip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path().join("daily");
+        let archive_root = temp_dir.path().join("archive");
+        let today = date(2024, 3, 15);
+
+        create(&base, "2024-01/15", Some("a.txt"));
+        let mut settings = settings(&base, &archive_root, ArchiveMode::Move);
+        settings.retention.trash_empty = false;
+        let journal = Journal::at(temp_dir.path().join(journal::JOURNAL_FILE));
+        let report = run(&settings, today, &journal).unwrap();
+        assert_eq!(report.archived.len(), 1);
+        assert_eq!((report.archived[0].files, report.archived[0].bytes), (1, 5));
+        assert!(archive_root.join("work/2024-01/15/a.txt").is_file());
+        assert!(!base.join("2024-01").exists(), "emptied month folder is removed");
+        assert_eq!(journal.operations().unwrap().len(), 1);
+
+        // 同一个月分两次压缩进同一个压缩包
+        create(&base, "2024-02/01", Some("b.txt"));
+        settings.retention.mode = ArchiveMode::ZipPerMonth;
+        run(&settings, today, &journal).unwrap();
+        create(&base, "2024-02/02", Some("c.txt"));
+        let report = run(&settings, today, &journal).unwrap();
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        assert!(!base.join("2024-02").exists());
+        // 压缩不记入操作日志
+        assert_eq!(journal.operations().unwrap().len(), 1);
+
+        let archive = ZipArchive::new(File::open(archive_root.join("work/2024-02.zip")).unwrap()).unwrap();
+        let mut names: Vec<_> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, ["2024-02/01/", "2024-02/01/b.txt", "2024-02/02/", "2024-02/02/c.txt"]);
+
+        let manifest = fs::read_to_string(archive_root.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.lines().count(), 3);
+        assert!(manifest.lines().all(|line| serde_json::from_str::<ManifestRecord>(line).is_ok()));
+    }
+
+    #[test]
+    fn test_plan_inferred_year() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path().join("daily");
+        let archive_root = temp_dir.path().join("archive");
+        create(&base, "0101", Some("a.txt"));
+
+        let mut settings = settings(&base, &archive_root, ArchiveMode::Move);
+        settings.profiles[0].folder_levels = vec![DateFormat::MMDD];
+        // 文件夹刚创建，年份推断为今年
+        let year = Local::now().year();
+        let plan = plan(&settings, date(year + 1, 3, 15)).unwrap();
+        assert_eq!(plan.items.len(), 1);
+        assert_eq!(plan.items[0].date, date(year, 1, 1));
+        assert_eq!(plan.items[0].kind, FolderKind::InferredYear);
+
+        // 推断的日期还在保留期内时不归档
+        assert!(super::plan(&settings, date(year, 1, 2)).unwrap().items.is_empty());
+    }
+}
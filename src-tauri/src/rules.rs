@@ -0,0 +1,306 @@
+//! 归档规则：按扩展名、通配符、正则、大小和来源目录决定文件放进当天文件夹的哪个子目录
+
+use crate::error::{AppError, AppResult};
+use crate::template;
+use globset::GlobBuilder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 一条规则：所有已设置的条件都满足时匹配，未设置任何条件的规则匹配所有文件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rule {
+    pub name: String,
+    /// 扩展名之一（不含点，不区分大小写），如 `["pdf", "docx"]`
+    pub extensions: Vec<String>,
+    /// 文件名通配符（不区分大小写），如 `IMG_*.jpg`
+    pub glob: Option<String>,
+    /// 文件名正则，如 `^invoice-\d+`
+    pub regex: Option<String>,
+    /// 文件大小下限（字节，含）
+    pub min_size: Option<u64>,
+    /// 文件大小上限（字节，含）
+    pub max_size: Option<u64>,
+    /// 文件所在的来源目录
+    pub source_dir: Option<String>,
+    pub action: RuleAction,
+    pub on_match: MatchPolicy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    /// 移入当天文件夹下的子目录，如 `docs` 或 `photos/raw`
+    MoveTo { subfolder: String },
+    /// 不移动，文件留在原处；之后的规则不再评估
+    Ignore,
+}
+
+impl Default for RuleAction {
+    fn default() -> Self {
+        RuleAction::MoveTo { subfolder: String::new() }
+    }
+}
+
+/// 规则匹配后是否继续评估后续规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchPolicy {
+    /// 第一条匹配的规则决定结果
+    #[default]
+    Stop,
+    /// 继续评估；之后匹配的 `move_to` 规则的子目录接在后面，如 `docs` + `invoices` → `docs/invoices`
+    Continue,
+}
+
+/// 被评估的文件
+#[derive(Debug, Clone)]
+pub struct FileFacts {
+    pub name: String,
+    pub size: Option<u64>,
+    pub source_dir: Option<PathBuf>,
+}
+
+impl FileFacts {
+    /// 读取文件名、大小和所在目录；文件不存在时大小为空，大小条件不会匹配
+    pub fn from_path(path: &Path) -> Self {
+        Self {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            size: std::fs::metadata(path).ok().map(|metadata| metadata.len()),
+            source_dir: path.parent().map(Path::to_path_buf),
+        }
+    }
+}
+
+/// 评估结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleOutcome {
+    /// 移入当天文件夹下的子目录，空路径表示文件夹本身
+    Move { subfolder: PathBuf },
+    Ignore,
+}
+
+/// 规则评估的详细结果，`test_rules` 命令据此显示哪些规则生效
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleEvaluation {
+    /// 依次匹配的规则序号（从 0 开始）和名称
+    pub matched: Vec<(usize, String)>,
+    pub outcome: RuleOutcome,
+}
+
+impl Rule {
+    pub fn validate(&self) -> AppResult<()> {
+        self.matches(&FileFacts {
+            name: String::new(),
+            size: None,
+            source_dir: None,
+        })?;
+
+        if let RuleAction::MoveTo { subfolder } = &self.action {
+            subfolder_path(subfolder)?;
+        }
+        Ok(())
+    }
+
+    /// 规则是否匹配文件；通配符或正则写错时返回错误
+    pub fn matches(&self, file: &FileFacts) -> AppResult<bool> {
+        let mut matched = true;
+
+        if !self.extensions.is_empty() {
+            let extension = file.name.rsplit_once('.').map(|(_, extension)| extension);
+            matched &= extension.is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(extension))
+            });
+        }
+
+        if let Some(pattern) = &self.glob {
+            let glob = GlobBuilder::new(pattern)
+                .case_insensitive(true)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| self.error(&format!("通配符无效: {}", e)))?;
+            matched &= glob.compile_matcher().is_match(&file.name);
+        }
+
+        if let Some(pattern) = &self.regex {
+            let regex = Regex::new(pattern).map_err(|e| self.error(&format!("正则表达式无效: {}", e)))?;
+            matched &= regex.is_match(&file.name);
+        }
+
+        if let Some(min_size) = self.min_size {
+            matched &= file.size.is_some_and(|size| size >= min_size);
+        }
+        if let Some(max_size) = self.max_size {
+            matched &= file.size.is_some_and(|size| size <= max_size);
+        }
+
+        if let Some(source_dir) = &self.source_dir {
+            matched &= file
+                .source_dir
+                .as_ref()
+                .is_some_and(|dir| same_dir(dir, Path::new(source_dir)));
+        }
+
+        Ok(matched)
+    }
+
+    fn error(&self, message: &str) -> AppError {
+        AppError::configuration(&format!("规则 {}: {}", self.name, message))
+    }
+}
+
+/// 按顺序评估规则，没有规则匹配时移入当天文件夹本身
+pub fn evaluate(rules: &[Rule], file: &FileFacts) -> AppResult<RuleEvaluation> {
+    let mut matched = Vec::new();
+    let mut subfolder = PathBuf::new();
+
+    for (index, rule) in rules.iter().enumerate() {
+        if !rule.matches(file)? {
+            continue;
+        }
+        matched.push((index, rule.name.clone()));
+
+        match &rule.action {
+            RuleAction::Ignore => {
+                return Ok(RuleEvaluation { matched, outcome: RuleOutcome::Ignore });
+            }
+            RuleAction::MoveTo { subfolder: next } => subfolder.push(subfolder_path(next)?),
+        }
+        if rule.on_match == MatchPolicy::Stop {
+            break;
+        }
+    }
+
+    Ok(RuleEvaluation { matched, outcome: RuleOutcome::Move { subfolder } })
+}
+
+pub fn validate(rules: &[Rule]) -> AppResult<()> {
+    rules.iter().try_for_each(Rule::validate)
+}
+
+/// 将 `docs/pdf` 或 `docs\pdf` 拆成逐级校验过的相对路径
+fn subfolder_path(subfolder: &str) -> AppResult<PathBuf> {
+    subfolder
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty())
+        .map(|part| template::validate_folder_name(part).map(|_| part))
+        .collect()
+}
+
+/// 比较目录时忽略末尾的分隔符，Windows 上不区分大小写
+fn same_dir(a: &Path, b: &Path) -> bool {
+    let normalize = |path: &Path| {
+        let text = path.to_string_lossy();
+        let text = text.trim_end_matches(['/', '\\']);
+        if cfg!(windows) {
+            text.replace('/', "\\").to_lowercase()
+        } else {
+            text.to_string()
+        }
+    };
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn file(name: &str, size: u64, source_dir: &str) -> FileFacts {
+        FileFacts {
+            name: name.to_string(),
+            size: Some(size),
+            source_dir: Some(PathBuf::from(source_dir)),
+        }
+    }
+
+    fn move_to(name: &str, subfolder: &str) -> Rule {
+        Rule {
+            name: name.to_string(),
+            action: RuleAction::MoveTo { subfolder: subfolder.to_string() },
+            ..Rule::default()
+        }
+    }
+
+    fn outcome(rules: &[Rule], file: &FileFacts) -> RuleOutcome {
+        evaluate(rules, file).unwrap().outcome
+    }
+
+    fn moved(subfolder: &str) -> RuleOutcome {
+        RuleOutcome::Move { subfolder: PathBuf::from(subfolder) }
+    }
+
+    #[test]
+    fn test_conditions() {
+        let pdf = Rule { extensions: vec!["PDF".to_string()], ..move_to("pdf", "docs") };
+        let photos = Rule { glob: Some("img_*.jpg".to_string()), ..move_to("photos", "photos") };
+        let invoices = Rule { regex: Some(r"^invoice-\d+".to_string()), ..move_to("invoices", "invoices") };
+        let big_logs = Rule {
+            extensions: vec!["log".to_string()],
+            min_size: Some(10 * MB),
+            action: RuleAction::Ignore,
+            ..Rule::default()
+        };
+        let from_desktop = Rule { source_dir: Some("/home/me/Desktop/".to_string()), ..move_to("desktop", "desktop") };
+        let rules = [pdf, photos, invoices, big_logs, from_desktop];
+
+        let downloads = "/home/me/Downloads";
+        assert_eq!(outcome(&rules, &file("paper.pdf", 1, downloads)), moved("docs"));
+        assert_eq!(outcome(&rules, &file("IMG_0001.JPG", 1, downloads)), moved("photos"));
+        assert_eq!(outcome(&rules, &file("IMG_0001.png", 1, downloads)), moved(""));
+        assert_eq!(outcome(&rules, &file("invoice-2024.xlsx", 1, downloads)), moved("invoices"));
+        assert_eq!(outcome(&rules, &file("my-invoice-2024.xlsx", 1, downloads)), moved(""));
+        assert_eq!(outcome(&rules, &file("app.log", 11 * MB, downloads)), RuleOutcome::Ignore);
+        assert_eq!(outcome(&rules, &file("app.log", MB, downloads)), moved(""));
+        assert_eq!(outcome(&rules, &file("note.txt", 1, "/home/me/Desktop")), moved("desktop"));
+
+        // 大小未知时大小条件不匹配
+        let unknown_size = FileFacts { size: None, ..file("app.log", 0, downloads) };
+        assert_eq!(outcome(&rules, &unknown_size), moved(""));
+    }
+
+    #[test]
+    fn test_first_match_and_continue() {
+        let docs = Rule { extensions: vec!["pdf".to_string()], ..move_to("docs", "docs") };
+        let invoices = Rule { glob: Some("invoice*".to_string()), ..move_to("invoices", "invoices") };
+        let file = file("invoice-1.pdf", 1, "/dl");
+
+        // 默认第一条匹配的规则生效
+        let evaluation = evaluate(&[docs.clone(), invoices.clone()], &file).unwrap();
+        assert_eq!(evaluation.outcome, moved("docs"));
+        assert_eq!(evaluation.matched, vec![(0, "docs".to_string())]);
+
+        // 继续评估时子目录逐级叠加，忽略规则立即结束
+        let docs = Rule { on_match: MatchPolicy::Continue, ..docs };
+        let evaluation = evaluate(&[docs.clone(), invoices.clone()], &file).unwrap();
+        assert_eq!(evaluation.outcome, moved("docs/invoices"));
+        assert_eq!(evaluation.matched.len(), 2);
+
+        let ignore_all = Rule { action: RuleAction::Ignore, ..Rule::default() };
+        assert_eq!(outcome(&[docs, ignore_all, invoices], &file), RuleOutcome::Ignore);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Rule { glob: Some("[".to_string()), ..Rule::default() }.validate().is_err());
+        assert!(Rule { regex: Some("(".to_string()), ..Rule::default() }.validate().is_err());
+        assert!(move_to("bad", "docs/../secret").validate().is_err());
+        assert!(move_to("nested", "docs\\pdf").validate().is_ok());
+
+        let rules: Vec<Rule> = serde_json::from_str(
+            r#"[{ "name": "pdf", "extensions": ["pdf"], "action": { "type": "move_to", "subfolder": "docs" } },
+                { "name": "big", "min_size": 10485760, "action": { "type": "ignore" }, "on_match": "continue" }]"#,
+        )
+        .unwrap();
+        assert!(validate(&rules).is_ok());
+        assert_eq!(rules[1].action, RuleAction::Ignore);
+    }
+}
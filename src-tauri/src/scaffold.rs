@@ -0,0 +1,174 @@
+use crate::error::{AppError, AppResult};
+use crate::template;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// 日期文件夹内的脚手架条目
+///
+/// 名称和文件内容中的 `{变量}` 会按 [`template::substitute`] 替换，
+/// 另外提供 `{folder_name}` 表示日期文件夹自身的名称。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScaffoldEntry {
+    Dir {
+        name: String,
+        #[serde(default)]
+        children: Vec<ScaffoldEntry>,
+    },
+    File {
+        name: String,
+        #[serde(default)]
+        content: String,
+    },
+}
+
+impl ScaffoldEntry {
+    fn name(&self) -> &str {
+        match self {
+            ScaffoldEntry::Dir { name, .. } | ScaffoldEntry::File { name, .. } => name,
+        }
+    }
+}
+
+/// 在日期文件夹中补齐脚手架
+///
+/// 只创建缺失的目录和文件，已存在的文件（可能已被用户编辑）不会被覆盖，可重复调用。
+/// 返回本次新建的路径，上级目录在前。
+pub fn apply(folder: &Path, entries: &[ScaffoldEntry], date: &NaiveDate) -> AppResult<Vec<PathBuf>> {
+    let folder_name = folder
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let variables = [("folder_name", folder_name.as_str())];
+
+    let mut created = Vec::new();
+    apply_entries(folder, entries, date, &variables, &mut created)?;
+    Ok(created)
+}
+
+/// 校验脚手架中的名称在替换变量后都是合法的文件/文件夹名
+pub fn validate(entries: &[ScaffoldEntry]) -> AppResult<()> {
+    for date in template::sample_dates() {
+        validate_entries(entries, &date)?;
+    }
+    Ok(())
+}
+
+fn apply_entries(
+    parent: &Path,
+    entries: &[ScaffoldEntry],
+    date: &NaiveDate,
+    variables: &[(&str, &str)],
+    created: &mut Vec<PathBuf>,
+) -> AppResult<()> {
+    for entry in entries {
+        let name = template::substitute(entry.name(), date, variables);
+        template::validate_folder_name(&name)?;
+        let path = parent.join(&name);
+
+        match entry {
+            ScaffoldEntry::Dir { children, .. } => {
+                if !path.exists() {
+                    fs::create_dir_all(&path).map_err(|e| {
+                        AppError::file_system(&format!("创建脚手架目录失败 {}: {}", path.display(), e))
+                    })?;
+                    created.push(path.clone());
+                }
+                apply_entries(&path, children, date, variables, created)?;
+            }
+            ScaffoldEntry::File { content, .. } => {
+                let content = template::substitute(content, date, variables);
+                if write_new_file(&path, &content)? {
+                    created.push(path);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_entries(entries: &[ScaffoldEntry], date: &NaiveDate) -> AppResult<()> {
+    for entry in entries {
+        template::validate_folder_name(&template::substitute(
+            entry.name(),
+            date,
+            &[("folder_name", "folder")],
+        ))?;
+        if let ScaffoldEntry::Dir { children, .. } = entry {
+            validate_entries(children, date)?;
+        }
+    }
+    Ok(())
+}
+
+/// 仅在文件不存在时写入，避免覆盖用户已修改的内容；返回是否新建了文件
+fn write_new_file(path: &Path, content: &str) -> AppResult<bool> {
+    match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => file.write_all(content.as_bytes()).map(|_| true).map_err(|e| {
+            AppError::file_system(&format!("写入脚手架文件失败 {}: {}", path.display(), e))
+        }),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(AppError::file_system(&format!(
+            "创建脚手架文件失败 {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn entries() -> Vec<ScaffoldEntry> {
+        vec![
+            ScaffoldEntry::Dir { name: "input".to_string(), children: Vec::new() },
+            ScaffoldEntry::Dir {
+                name: "notes".to_string(),
+                children: vec![ScaffoldEntry::File {
+                    name: "notes.md".to_string(),
+                    content: "# {date} {weekday}\n".to_string(),
+                }],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_apply_scaffold() {
+        let temp_dir = tempdir().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+
+        apply(temp_dir.path(), &entries(), &date).unwrap();
+
+        assert!(temp_dir.path().join("input").is_dir());
+        let notes = temp_dir.path().join("notes").join("notes.md");
+        assert_eq!(fs::read_to_string(notes).unwrap(), "# 2024-03-15 Fri\n");
+    }
+
+    #[test]
+    fn test_apply_is_idempotent() {
+        let temp_dir = tempdir().unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        apply(temp_dir.path(), &entries(), &date).unwrap();
+
+        let notes = temp_dir.path().join("notes").join("notes.md");
+        fs::write(&notes, "edited").unwrap();
+        fs::remove_dir(temp_dir.path().join("input")).unwrap();
+
+        let created = apply(temp_dir.path(), &entries(), &date).unwrap();
+
+        assert_eq!(created, vec![temp_dir.path().join("input")]);
+        assert_eq!(fs::read_to_string(notes).unwrap(), "edited");
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_names() {
+        let bad = vec![ScaffoldEntry::Dir { name: "a/b".to_string(), children: Vec::new() }];
+        assert!(validate(&bad).is_err());
+        assert!(validate(&entries()).is_ok());
+    }
+}
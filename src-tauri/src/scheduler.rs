@@ -0,0 +1,281 @@
+use crate::folder::FolderManager;
+use crate::journal::Journal;
+use crate::settings::AppSettings;
+use crate::tray;
+use chrono::{Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+use std::ops::ControlFlow;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager, Runtime};
+
+/// 单次休眠的上限。休眠被系统挂起或时钟跳变打断时，最多延迟这么久就能发现日期变化
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 可注入的时钟，便于在测试中模拟时间流逝
+pub trait Clock {
+    /// 当前本地时间
+    fn now(&self) -> NaiveDateTime;
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// 触发创建的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// 跨过了午夜（包括从睡眠中恢复、系统时间被调整到另一天）
+    DayChanged,
+    /// 到达设置的每日定时
+    ScheduledTime,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderCreatedEvent {
+    pub profile_id: String,
+    pub path: String,
+    pub trigger: Trigger,
+}
+
+/// 决定何时触发创建的纯逻辑，不依赖真实时间
+pub struct Scheduler {
+    current_day: NaiveDate,
+    daily_time: Option<NaiveTime>,
+    /// 每日定时最近一次触发的日期
+    time_fired_on: Option<NaiveDate>,
+}
+
+impl Scheduler {
+    pub fn new(now: NaiveDateTime, daily_time: Option<NaiveTime>) -> Self {
+        Self {
+            current_day: now.date(),
+            daily_time,
+            time_fired_on: Self::passed_today(now, daily_time),
+        }
+    }
+
+    /// 更新每日定时；新时间若今天已过，不会立即补触发
+    pub fn set_daily_time(&mut self, daily_time: Option<NaiveTime>, now: NaiveDateTime) {
+        if self.daily_time != daily_time {
+            self.daily_time = daily_time;
+            self.time_fired_on = Self::passed_today(now, daily_time);
+        }
+    }
+
+    /// 检查当前时间是否应该触发创建
+    pub fn tick(&mut self, now: NaiveDateTime) -> Option<Trigger> {
+        let today = now.date();
+
+        if today != self.current_day {
+            self.current_day = today;
+            // 新的一天已经创建过，今天已过的定时无需再触发
+            self.time_fired_on = Self::passed_today(now, self.daily_time);
+            return Some(Trigger::DayChanged);
+        }
+
+        match self.daily_time {
+            Some(time) if now.time() >= time && self.time_fired_on != Some(today) => {
+                self.time_fired_on = Some(today);
+                Some(Trigger::ScheduledTime)
+            }
+            _ => None,
+        }
+    }
+
+    /// 距离下次需要检查的时长：下一个午夜或今天的定时，且不超过 [`POLL_INTERVAL`]
+    pub fn until_next_wake(&self, now: NaiveDateTime) -> Duration {
+        let today = now.date();
+        let mut next = (today + ChronoDuration::days(1)).and_time(NaiveTime::MIN);
+
+        if let Some(time) = self.daily_time {
+            let scheduled = today.and_time(time);
+            if self.time_fired_on != Some(today) && scheduled > now && scheduled < next {
+                next = scheduled;
+            }
+        }
+
+        (next - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO)
+            .min(POLL_INTERVAL)
+    }
+
+    fn passed_today(now: NaiveDateTime, daily_time: Option<NaiveTime>) -> Option<NaiveDate> {
+        match daily_time {
+            Some(time) if now.time() >= time => Some(now.date()),
+            _ => None,
+        }
+    }
+}
+
+/// 调度循环：按 [`Scheduler`] 的结果休眠和触发，直到 `on_trigger` 返回 `Break`
+///
+/// `daily_time` 在每次醒来时调用，以便读取最新设置。
+pub fn run<C: Clock>(
+    clock: &C,
+    mut daily_time: impl FnMut() -> Option<NaiveTime>,
+    mut on_trigger: impl FnMut(Trigger) -> ControlFlow<()>,
+) {
+    let mut scheduler = Scheduler::new(clock.now(), daily_time());
+
+    loop {
+        clock.sleep(scheduler.until_next_wake(clock.now()));
+
+        let now = clock.now();
+        scheduler.set_daily_time(daily_time(), now);
+        if let Some(trigger) = scheduler.tick(now) {
+            if on_trigger(trigger).is_break() {
+                return;
+            }
+        }
+    }
+}
+
+/// 在后台线程启动调度器，触发时创建今天的文件夹并通知前端
+pub fn start<R: Runtime>(app: tauri::AppHandle<R>) {
+    std::thread::spawn(move || {
+        run(
+            &SystemClock,
+            || current_settings(&app).and_then(|settings| settings.auto_create_time),
+            |trigger| {
+                create_for_trigger(&app, trigger);
+                ControlFlow::Continue(())
+            },
+        );
+    });
+}
+
+fn current_settings<R: Runtime>(app: &tauri::AppHandle<R>) -> Option<AppSettings> {
+    let state = app.state::<Mutex<AppSettings>>();
+    let settings = state.lock().ok()?;
+    Some(settings.clone())
+}
+
+fn create_for_trigger<R: Runtime>(app: &tauri::AppHandle<R>, trigger: Trigger) {
+    let Some(settings) = current_settings(app) else {
+        eprintln!("Scheduler: failed to read settings");
+        return;
+    };
+
+    // 日期变了，托盘提示中的节假日信息也要更新
+    tray::refresh(app, &settings);
+
+    let today = Local::now().date_naive();
+    if !settings.auto_create_daily || !settings.calendar.allows_auto_create(&today) {
+        return;
+    }
+
+    for profile in settings.profiles.iter().filter(|profile| profile.auto_create) {
+        match Journal::global().and_then(|journal| FolderManager::create_today_folder(profile, &journal)) {
+            Ok(path) => {
+                let event = FolderCreatedEvent { profile_id: profile.id.clone(), path, trigger };
+                let _ = app.emit("folder-created", event);
+            }
+            Err(e) => {
+                eprintln!("Scheduler: failed to create today's folder for {}: {}", profile.id, e);
+                let _ = app.emit("folder-create-failed", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    struct FakeClock {
+        now: Cell<NaiveDateTime>,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> NaiveDateTime {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.now.set(self.now.get() + ChronoDuration::from_std(duration).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_fires_at_midnight_and_daily_time() {
+        let clock = FakeClock { now: Cell::new(at(15, 23, 0)) };
+        let fired = RefCell::new(Vec::new());
+
+        run(
+            &clock,
+            || NaiveTime::from_hms_opt(8, 30, 0),
+            |trigger| {
+                fired.borrow_mut().push((trigger, clock.now()));
+                if fired.borrow().len() == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+
+        assert_eq!(
+            fired.into_inner(),
+            vec![
+                (Trigger::DayChanged, at(16, 0, 0)),
+                (Trigger::ScheduledTime, at(16, 8, 30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detects_clock_jumps() {
+        let mut scheduler = Scheduler::new(at(15, 22, 0), None);
+        assert_eq!(scheduler.tick(at(15, 22, 1)), None);
+
+        // 系统挂起一整天后恢复
+        assert_eq!(scheduler.tick(at(17, 9, 0)), Some(Trigger::DayChanged));
+        assert_eq!(scheduler.tick(at(17, 9, 1)), None);
+
+        // 时钟被往回调到前一天
+        assert_eq!(scheduler.tick(at(16, 9, 0)), Some(Trigger::DayChanged));
+    }
+
+    #[test]
+    fn test_daily_time_not_repeated_after_rollover() {
+        let time = NaiveTime::from_hms_opt(8, 0, 0);
+        let mut scheduler = Scheduler::new(at(15, 7, 0), time);
+
+        assert_eq!(scheduler.tick(at(15, 8, 0)), Some(Trigger::ScheduledTime));
+        assert_eq!(scheduler.tick(at(15, 9, 0)), None);
+
+        // 睡过了第二天的定时，只触发一次
+        assert_eq!(scheduler.tick(at(16, 10, 0)), Some(Trigger::DayChanged));
+        assert_eq!(scheduler.tick(at(16, 10, 1)), None);
+    }
+
+    #[test]
+    fn test_wake_is_capped_by_poll_interval() {
+        let scheduler = Scheduler::new(at(15, 12, 0), None);
+        assert_eq!(scheduler.until_next_wake(at(15, 12, 0)), POLL_INTERVAL);
+        assert_eq!(
+            scheduler.until_next_wake(at(15, 23, 59) + ChronoDuration::seconds(30)),
+            Duration::from_secs(30)
+        );
+    }
+}
@@ -1,76 +1,568 @@
+use crate::calendar::CalendarPolicy;
+use crate::control_api::ControlApiSettings;
 use crate::error::{AppError, AppResult};
+use crate::fs_util;
+use crate::hooks::{self, Hook};
+use crate::inbox::InboxSettings;
+use crate::retention::RetentionSettings;
+use crate::rules::{self, Rule};
+use crate::migration::{self, CURRENT_VERSION};
+use crate::scaffold::{self, ScaffoldEntry};
+use crate::template;
+use chrono::format::Parsed;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::Manager;
 
+/// 缺失的字段取默认值，不认识的字段被忽略，以兼容新旧版本的配置文件
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
-    pub folder_path: String,
-    pub date_format: DateFormat,
+    /// 配置文件结构版本，见 [`migration`]
+    pub version: u32,
+    /// 各个独立的日期文件夹配置，第一个为默认配置
+    pub profiles: Vec<Profile>,
     pub auto_start: bool,
-    pub auto_create_on_startup: bool,
+    /// 应用保持运行时，在午夜自动创建新一天的文件夹
+    pub auto_create_daily: bool,
+    /// 每日额外检查一次的时间，如 "08:30"
+    pub auto_create_time: Option<NaiveTime>,
+    /// 工作日策略，决定启动时和定时创建是否跳过节假日
+    pub calendar: CalendarPolicy,
+    /// 供其他工具调用的本地控制接口
+    pub control_api: ControlApiSettings,
+    /// 把下载目录等来源中的新文件移入今天的文件夹
+    pub inbox: InboxSettings,
+    /// 归档时决定文件放进哪个子目录的规则，按顺序评估
+    pub rules: Vec<Rule>,
+    /// 定期归档早于保留天数的日期文件夹
+    pub retention: RetentionSettings,
+}
+
+/// 一个历史配置备份
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsBackup {
+    pub index: usize,
+    pub path: String,
+    pub modified: Option<DateTime<Local>>,
+}
+
+/// 配置文件损坏或版本过新被隔离后的恢复记录，供前端提示用户
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigRecovery {
+    /// 解析失败的原因，含出错的行列号
+    pub error: AppError,
+    /// 损坏文件被移动到的位置
+    pub quarantined_path: String,
+    /// 用于恢复的备份文件；为空表示已重置为默认设置
+    pub restored_from: Option<String>,
 }
 
+/// 一组独立的日期文件夹配置，如“工作”和“个人”
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub folder_path: String,
+    pub date_format: DateFormat,
+    /// 分层目录模板，依次对应每一级文件夹（如年/月/日）；为空时只按 `date_format` 创建一级
+    pub folder_levels: Vec<DateFormat>,
+    /// 新建日期文件夹时在其中生成的子目录和种子文件
+    pub scaffold: Vec<ScaffoldEntry>,
+    /// 启动时及跨日时是否自动创建
+    pub auto_create: bool,
+    /// 新建日期文件夹后依次执行的命令
+    pub hooks: Vec<Hook>,
+}
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+/// 与 tauri.conf.json 中的 `identifier` 一致，Tauri 的配置目录以此命名
+pub const APP_IDENTIFIER: &str = "com.smartfolderzen.app";
+
+/// 保留的历史备份数量
+pub const SETTINGS_BACKUP_COUNT: usize = 5;
+
+/// 最近一次成功加载或保存的配置副本
+fn last_good_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name("settings.json.bak")
+}
+
+/// 第 `index` 个历史备份，序号越大越旧
+fn backup_path(config_path: &Path, index: usize) -> PathBuf {
+    config_path.with_file_name(format!("settings.json.bak.{}", index))
+}
+
+/// 覆盖配置前，将当前文件轮转进历史备份；内容没有变化时不产生新备份
+fn rotate_backups(config_path: &Path, new_content: &str) -> std::io::Result<()> {
+    let current = match fs::read_to_string(config_path) {
+        Ok(current) => current,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if current == new_content {
+        return Ok(());
+    }
+
+    for index in (1..SETTINGS_BACKUP_COUNT).rev() {
+        let from = backup_path(config_path, index);
+        if from.exists() {
+            fs::rename(&from, backup_path(config_path, index + 1))?;
+        }
+    }
+    fs_util::write_atomic(&backup_path(config_path, 1), current.as_bytes())
+}
+
+fn format_error(error: serde_json::Error) -> AppError {
+    AppError::configuration(&format!("配置文件格式错误: {}", error))
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DateFormat {
     MMDD,
     YYYYMMDD,
+    /// 自定义命名模板，语法见 [`template::render`]
+    Custom(String),
 }
 
 impl DateFormat {
-    pub fn format_date(&self, date: &chrono::NaiveDate) -> String {
+    pub fn format_date(&self, date: &chrono::NaiveDate) -> AppResult<String> {
+        match self {
+            DateFormat::MMDD => Ok(date.format("%m%d").to_string()),
+            DateFormat::YYYYMMDD => Ok(date.format("%Y-%m-%d").to_string()),
+            DateFormat::Custom(pattern) => template::render(pattern, date),
+        }
+    }
+
+    /// 把文件夹名中的日期字段解析进 `parsed`，见 [`template::parse_into`]
+    pub fn parse_into(&self, name: &str, parsed: &mut Parsed) -> bool {
+        match self {
+            DateFormat::MMDD => template::parse_into("%m%d", name, parsed),
+            DateFormat::YYYYMMDD => template::parse_into("%Y-%m-%d", name, parsed),
+            DateFormat::Custom(pattern) => template::parse_into(pattern, name, parsed),
+        }
+    }
+
+    /// 校验格式能生成合法的文件夹名
+    pub fn validate(&self) -> AppResult<()> {
         match self {
-            DateFormat::MMDD => date.format("%m%d").to_string(),
-            DateFormat::YYYYMMDD => date.format("%Y-%m-%d").to_string(),
+            DateFormat::MMDD | DateFormat::YYYYMMDD => Ok(()),
+            DateFormat::Custom(pattern) => template::validate_pattern(pattern),
         }
     }
 }
 
+/// 不晚于 `reference` 的最近一个有该月日的年份
+///
+/// 日期文件夹在当天创建，之后的修改只会让修改时间更晚，所以用修改时间作参考能还原缺少的年份。
+pub fn infer_year(month: u32, day: u32, reference: NaiveDate) -> Option<i32> {
+    // 2 月 29 日最多要往前找 8 年（如 1900 年不是闰年）
+    (reference.year() - 8..=reference.year())
+        .rev()
+        .find(|year| NaiveDate::from_ymd_opt(*year, month, day).is_some_and(|date| date <= reference))
+}
+
+/// 按命名层级逐级解析文件夹名，缺少年份时用 `year_for(月, 日)` 补出
+fn date_from_names(
+    levels: &[DateFormat],
+    names: &[&str],
+    year_for: impl FnOnce(u32, u32) -> Option<i32>,
+) -> Option<NaiveDate> {
+    if names.len() != levels.len() {
+        return None;
+    }
+
+    let mut parsed = Parsed::new();
+    for (format, name) in levels.iter().zip(names) {
+        if !format.parse_into(name, &mut parsed) {
+            return None;
+        }
+    }
+
+    let date = match parsed.to_naive_date() {
+        Ok(date) => date,
+        Err(_) if parsed.year().is_none() => {
+            let year = year_for(parsed.month()?, parsed.day()?)?;
+            parsed.set_year(year.into()).ok()?;
+            parsed.to_naive_date().ok()?
+        }
+        Err(_) => return None,
+    };
+
+    // 重新渲染比对，排除模板中被跳过的部分不一致的名称
+    let matches = levels
+        .iter()
+        .zip(names)
+        .all(|(format, name)| format.format_date(&date).is_ok_and(|rendered| rendered == *name));
+    matches.then_some(date)
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
+            version: CURRENT_VERSION,
+            profiles: vec![Profile::default()],
+            auto_start: true,
+            auto_create_daily: true,
+            auto_create_time: None,
+            calendar: CalendarPolicy::default(),
+            control_api: ControlApiSettings::default(),
+            inbox: InboxSettings::default(),
+            rules: Vec::new(),
+            retention: RetentionSettings::default(),
+        }
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            id: DEFAULT_PROFILE_ID.to_string(),
+            name: "默认".to_string(),
             folder_path: dirs::desktop_dir()
                 .unwrap_or_else(|| PathBuf::from("."))
                 .to_string_lossy()
                 .to_string(),
             date_format: DateFormat::YYYYMMDD,
-            auto_start: true,
-            auto_create_on_startup: true,
+            folder_levels: Vec::new(),
+            scaffold: Vec::new(),
+            auto_create: true,
+            hooks: Vec::new(),
         }
     }
 }
 
 impl AppSettings {
     pub fn load(app: &tauri::AppHandle) -> AppResult<Self> {
-        let config_path = Self::get_config_path(app)?;
-        
+        Self::load_from(&Self::get_config_path(app)?)
+    }
+
+    /// 从指定文件加载配置，旧版本的配置会先备份再升级写回
+    pub fn load_from(config_path: &Path) -> AppResult<Self> {
         if !config_path.exists() {
             let default_settings = Self::default();
-            default_settings.save(app)?;
+            default_settings.save_to(config_path)?;
             return Ok(default_settings);
         }
 
-        let content = fs::read_to_string(&config_path)
+        let content = fs::read_to_string(config_path)
             .map_err(|_| AppError::configuration("无法读取配置文件"))?;
-        
-        serde_json::from_str(&content)
-            .map_err(|_| AppError::configuration("配置文件格式错误"))
+        let (settings, upgraded_from) = Self::parse(&content)?;
+        settings.finish_load(config_path, &content, upgraded_from)?;
+
+        Ok(settings)
+    }
+
+    /// 只读取配置，不创建默认配置、不写回升级后的内容，也不更新备份；供命令行使用
+    pub fn read_from(config_path: &Path) -> AppResult<Self> {
+        if !config_path.exists() {
+            return Err(AppError::configuration(&format!("配置文件不存在: {}", config_path.display())));
+        }
+        let content = fs::read_to_string(config_path)
+            .map_err(|_| AppError::configuration("无法读取配置文件"))?;
+        Self::parse(&content).map(|(settings, _)| settings)
+    }
+
+    /// 重新读取被外部修改的配置。与启动加载不同，文件缺失或内容无效时直接报错，
+    /// 不会创建默认配置或隔离文件
+    pub fn reload_from(config_path: &Path) -> AppResult<Self> {
+        let content = fs::read_to_string(config_path)
+            .map_err(|_| AppError::configuration("无法读取配置文件"))?;
+        let (settings, upgraded_from) = Self::parse(&content)?;
+        settings.validate()?;
+        settings.finish_load(config_path, &content, upgraded_from)?;
+
+        Ok(settings)
+    }
+
+    /// 启动时加载配置：文件损坏或来自更新版本的程序时将其隔离为 `settings.json.corrupt-<时间>`，
+    /// 并从最近一次可用的备份恢复（没有备份时使用默认设置）
+    pub fn load_or_recover(app: &tauri::AppHandle) -> AppResult<(Self, Option<ConfigRecovery>)> {
+        Self::load_or_recover_from(&Self::get_config_path(app)?)
+    }
+
+    pub fn load_or_recover_from(config_path: &Path) -> AppResult<(Self, Option<ConfigRecovery>)> {
+        if !config_path.exists() {
+            return Ok((Self::load_from(config_path)?, None));
+        }
+
+        // 读取失败（如权限问题）不代表文件损坏，不做隔离
+        let content = fs::read_to_string(config_path)
+            .map_err(|_| AppError::configuration("无法读取配置文件"))?;
+
+        match Self::parse(&content) {
+            Ok((settings, upgraded_from)) => {
+                settings.finish_load(config_path, &content, upgraded_from)?;
+                Ok((settings, None))
+            }
+            Err(error) => Self::recover(config_path, error).map(|(settings, recovery)| {
+                (settings, Some(recovery))
+            }),
+        }
+    }
+
+    /// 解析配置内容并升级到当前版本，返回配置和升级前的版本
+    fn parse(content: &str) -> AppResult<(Self, Option<u32>)> {
+        let mut value: serde_json::Value =
+            serde_json::from_str(content).map_err(format_error)?;
+        let upgraded_from = migration::migrate(&mut value)?;
+
+        // 未经升级的内容直接从原文解析，以便错误信息带上行列号
+        let settings = match upgraded_from {
+            None => serde_json::from_str(content),
+            Some(_) => serde_json::from_value(value),
+        }
+        .map_err(format_error)?;
+
+        Ok((settings, upgraded_from))
+    }
+
+    /// 加载成功后的收尾：备份并写回升级后的配置，记录为最近一次可用的配置
+    fn finish_load(
+        &self,
+        config_path: &Path,
+        content: &str,
+        upgraded_from: Option<u32>,
+    ) -> AppResult<()> {
+        if let Some(version) = upgraded_from {
+            let backup_path = config_path.with_file_name(format!("settings.json.v{}.bak", version));
+            fs_util::write_atomic(&backup_path, content.as_bytes())
+                .map_err(|_| AppError::configuration("无法备份旧版配置文件"))?;
+            return self.write_to(config_path);
+        }
+
+        fs_util::write_atomic(&last_good_path(config_path), content.as_bytes())
+            .map_err(|_| AppError::configuration("无法备份配置文件"))
+    }
+
+    fn recover(config_path: &Path, error: AppError) -> AppResult<(Self, ConfigRecovery)> {
+        let stamp = Local::now().format("%Y%m%d-%H%M%S");
+        let quarantined_path = config_path.with_file_name(format!("settings.json.corrupt-{}", stamp));
+        fs::rename(config_path, &quarantined_path)
+            .map_err(|_| AppError::configuration("无法隔离损坏的配置文件"))?;
+
+        // 依次尝试最近一次可用的副本和历史备份（由新到旧）
+        let candidates = std::iter::once(last_good_path(config_path))
+            .chain((1..=SETTINGS_BACKUP_COUNT).map(|index| backup_path(config_path, index)));
+        let restored = candidates
+            .filter_map(|path| {
+                let content = fs::read_to_string(&path).ok()?;
+                let (settings, _) = Self::parse(&content).ok()?;
+                Some((settings, path))
+            })
+            .next();
+
+        let (settings, restored_from) = match restored {
+            Some((settings, path)) => (settings, Some(path.to_string_lossy().to_string())),
+            None => (Self::default(), None),
+        };
+        settings.write_to(config_path)?;
+
+        eprintln!(
+            "Settings file could not be loaded ({}), moved to {}",
+            error,
+            quarantined_path.display()
+        );
+
+        Ok((
+            settings,
+            ConfigRecovery {
+                error,
+                quarantined_path: quarantined_path.to_string_lossy().to_string(),
+                restored_from,
+            },
+        ))
     }
 
     pub fn save(&self, app: &tauri::AppHandle) -> AppResult<()> {
-        let config_path = Self::get_config_path(app)?;
-        
+        self.save_to(&Self::get_config_path(app)?)
+    }
+
+    /// 校验后保存到指定文件
+    pub fn save_to(&self, config_path: &Path) -> AppResult<()> {
+        self.validate()?;
+        self.write_to(config_path)
+    }
+
+    fn write_to(&self, config_path: &Path) -> AppResult<()> {
         if let Some(parent) = config_path.parent() {
             fs::create_dir_all(parent)
                 .map_err(|_| AppError::configuration("无法创建配置目录"))?;
         }
 
-        let content = serde_json::to_string_pretty(self)
+        let settings = Self { version: CURRENT_VERSION, ..self.clone() };
+        let content = serde_json::to_string_pretty(&settings)
             .map_err(|_| AppError::configuration("无法序列化配置"))?;
         
-        fs::write(&config_path, content)
-            .map_err(|_| AppError::configuration("无法保存配置文件"))
+        rotate_backups(config_path, &content)
+            .map_err(|_| AppError::configuration("无法备份配置文件"))?;
+        fs_util::write_atomic(config_path, content.as_bytes())
+            .map_err(|_| AppError::configuration("无法保存配置文件"))?;
+        fs_util::write_atomic(&last_good_path(config_path), content.as_bytes())
+            .map_err(|_| AppError::configuration("无法备份配置文件"))
+    }
+
+    /// 列出历史备份，序号 1 为最近一次保存前的配置
+    pub fn list_backups(app: &tauri::AppHandle) -> AppResult<Vec<SettingsBackup>> {
+        Ok(Self::list_backups_for(&Self::get_config_path(app)?))
+    }
+
+    pub fn list_backups_for(config_path: &Path) -> Vec<SettingsBackup> {
+        (1..=SETTINGS_BACKUP_COUNT)
+            .filter_map(|index| {
+                let path = backup_path(config_path, index);
+                let metadata = fs::metadata(&path).ok()?;
+                Some(SettingsBackup {
+                    index,
+                    path: path.to_string_lossy().to_string(),
+                    modified: metadata.modified().ok().map(DateTime::<Local>::from),
+                })
+            })
+            .collect()
+    }
+
+    /// 用指定的历史备份替换当前配置；当前配置会进入备份，因此恢复本身也可以撤销
+    pub fn restore_backup(app: &tauri::AppHandle, index: usize) -> AppResult<Self> {
+        Self::restore_backup_from(&Self::get_config_path(app)?, index)
+    }
+
+    pub fn restore_backup_from(config_path: &Path, index: usize) -> AppResult<Self> {
+        if !(1..=SETTINGS_BACKUP_COUNT).contains(&index) {
+            return Err(AppError::configuration(&format!("备份序号无效: {}", index)));
+        }
+
+        let content = fs::read_to_string(backup_path(config_path, index))
+            .map_err(|_| AppError::configuration(&format!("找不到备份 {}", index)))?;
+        let (settings, _) = Self::parse(&content)?;
+        settings.save_to(config_path)?;
+
+        Ok(settings)
+    }
+
+    /// 校验除路径以外的设置项
+    pub fn validate(&self) -> AppResult<()> {
+        if self.profiles.is_empty() {
+            return Err(AppError::configuration("至少需要一个配置"));
+        }
+
+        for (index, profile) in self.profiles.iter().enumerate() {
+            if self.profiles[..index].iter().any(|other| other.id == profile.id) {
+                return Err(AppError::configuration(&format!(
+                    "配置 ID 重复: {}",
+                    profile.id
+                )));
+            }
+            profile.validate()?;
+        }
+
+        rules::validate(&self.rules)?;
+
+        self.retention.validate()?;
+        for id in &self.retention.profile_ids {
+            self.profile(Some(id))?;
+        }
+
+        // 收件箱归档的目标配置必须存在
+        if self.inbox.enabled {
+            self.profile(self.inbox.profile_id.as_deref())?;
+        }
+
+        Ok(())
+    }
+
+    /// 校验所有配置的基础路径
+    pub fn validate_paths(&self) -> AppResult<()> {
+        self.profiles.iter().try_for_each(Profile::validate_path)
+    }
+
+    /// 按 ID 查找配置，未指定时返回默认配置
+    pub fn profile(&self, id: Option<&str>) -> AppResult<&Profile> {
+        match id {
+            Some(id) => self
+                .profiles
+                .iter()
+                .find(|profile| profile.id == id)
+                .ok_or_else(|| AppError::configuration(&format!("找不到配置: {}", id))),
+            None => self
+                .profiles
+                .first()
+                .ok_or_else(|| AppError::configuration("至少需要一个配置")),
+        }
+    }
+
+    /// 不依赖 Tauri 运行时的配置文件路径（命令行模式使用），与 `get_config_path` 指向同一文件
+    pub fn default_config_path() -> AppResult<PathBuf> {
+        dirs::config_dir()
+            .map(|dir| dir.join(APP_IDENTIFIER).join("settings.json"))
+            .ok_or_else(|| AppError::configuration("无法获取配置目录"))
+    }
+
+    pub fn get_config_path(app: &tauri::AppHandle) -> AppResult<PathBuf> {
+        app.path().app_config_dir()
+            .map(|path| path.join("settings.json"))
+            .map_err(|_| AppError::configuration("无法获取配置目录"))
+    }
+}
+
+impl Profile {
+    /// 校验命名模板、脚手架和钩子
+    pub fn validate(&self) -> AppResult<()> {
+        if self.id.trim().is_empty() {
+            return Err(AppError::configuration("配置 ID 不能为空"));
+        }
+
+        for format in self.naming_levels() {
+            format.validate()?;
+        }
+        scaffold::validate(&self.scaffold)?;
+        hooks::validate(&self.hooks)?;
+
+        // 相邻两天必须生成不同的路径，否则每天都会落到同一个文件夹
+        let [first, second] = template::sample_dates();
+        if self.relative_folder_path(&first)? == self.relative_folder_path(&second)? {
+            return Err(AppError::configuration("命名模板必须包含日期字段"));
+        }
+
+        Ok(())
+    }
+
+    /// 实际生效的命名层级
+    pub fn naming_levels(&self) -> &[DateFormat] {
+        if self.folder_levels.is_empty() {
+            std::slice::from_ref(&self.date_format)
+        } else {
+            &self.folder_levels
+        }
+    }
+
+    /// 指定日期的文件夹相对于 `folder_path` 的路径
+    pub fn relative_folder_path(&self, date: &chrono::NaiveDate) -> AppResult<PathBuf> {
+        self.naming_levels()
+            .iter()
+            .map(|format| format.format_date(date))
+            .collect()
+    }
+
+    /// 从相对于 `folder_path` 的路径反推日期；路径不是按本配置生成的，或缺少年份（如 MMDD）时返回 `None`
+    pub fn date_from_relative_path(&self, relative: &Path) -> Option<chrono::NaiveDate> {
+        self.date_from_relative_path_with(relative, |_, _| None)
+    }
+
+    /// 同 [`Self::date_from_relative_path`]，名称中没有年份时用 `year_for(月, 日)` 补出年份
+    pub fn date_from_relative_path_with(
+        &self,
+        relative: &Path,
+        year_for: impl FnOnce(u32, u32) -> Option<i32>,
+    ) -> Option<chrono::NaiveDate> {
+        let names = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        date_from_names(self.naming_levels(), &names, year_for)
     }
 
     pub fn validate_path(&self) -> AppResult<()> {
@@ -94,10 +586,88 @@ impl AppSettings {
             Err(_) => Err(AppError::permission_denied("没有写入权限")),
         }
     }
+}
 
-    fn get_config_path(app: &tauri::AppHandle) -> AppResult<PathBuf> {
-        app.path().app_config_dir()
-            .map(|path| path.join("settings.json"))
-            .map_err(|_| AppError::configuration("无法获取配置目录"))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corrupt_config_is_quarantined_and_restored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+
+        let good = AppSettings { auto_start: false, ..AppSettings::default() };
+        good.save_to(&config_path).unwrap();
+        fs::write(&config_path, "{\n  \"version\": 2,\n  \"profiles\": [\n").unwrap();
+
+        let (settings, recovery) = AppSettings::load_or_recover_from(&config_path).unwrap();
+        let recovery = recovery.unwrap();
+
+        assert!(!settings.auto_start);
+        assert!(recovery.error.message.contains("line 4"), "{}", recovery.error.message);
+        assert!(recovery.restored_from.is_some());
+        assert!(recovery.quarantined_path.contains("settings.json.corrupt-"));
+        assert!(fs::read_to_string(&recovery.quarantined_path).unwrap().contains("profiles"));
+        assert!(AppSettings::load_from(&config_path).is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_corrupt_config_without_backup_resets_to_defaults() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+        fs::write(&config_path, "{ \"profiles\": 42 }").unwrap();
+
+        let (settings, recovery) = AppSettings::load_or_recover_from(&config_path).unwrap();
+
+        assert!(recovery.unwrap().restored_from.is_none());
+        assert_eq!(settings.profiles.len(), 1);
+    }
+
+    #[test]
+    fn test_save_rotates_backups() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("settings.json");
+
+        for round in 0..SETTINGS_BACKUP_COUNT + 2 {
+            let settings = AppSettings {
+                auto_create_time: NaiveTime::from_hms_opt(round as u32, 0, 0),
+                ..AppSettings::default()
+            };
+            settings.save_to(&config_path).unwrap();
+            // 内容不变时不产生新备份
+            settings.save_to(&config_path).unwrap();
+        }
+
+        let backups = AppSettings::list_backups_for(&config_path);
+        assert_eq!(backups.len(), SETTINGS_BACKUP_COUNT);
+
+        let restored = AppSettings::restore_backup_from(&config_path, 1).unwrap();
+        assert_eq!(restored.auto_create_time, NaiveTime::from_hms_opt(SETTINGS_BACKUP_COUNT as u32, 0, 0));
+        assert_eq!(
+            AppSettings::load_from(&config_path).unwrap().auto_create_time,
+            restored.auto_create_time
+        );
+
+        // 恢复前的配置进入了备份 1，可以再恢复回去
+        let undone = AppSettings::restore_backup_from(&config_path, 1).unwrap();
+        assert_eq!(undone.auto_create_time, NaiveTime::from_hms_opt(SETTINGS_BACKUP_COUNT as u32 + 1, 0, 0));
+        assert!(AppSettings::restore_backup_from(&config_path, 0).is_err());
+    }
+
+    #[test]
+    fn test_profile_lookup_and_validation() {
+        let work = Profile { id: "work".to_string(), ..Profile::default() };
+        let mut settings = AppSettings {
+            profiles: vec![Profile::default(), work],
+            ..AppSettings::default()
+        };
+
+        assert_eq!(settings.profile(Some("work")).unwrap().id, "work");
+        assert!(settings.profile(Some("missing")).is_err());
+        assert!(settings.validate().is_ok());
+
+        settings.profiles[1].id = DEFAULT_PROFILE_ID.to_string();
+        assert!(settings.validate().is_err());
+    }
+}
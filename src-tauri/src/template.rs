@@ -0,0 +1,303 @@
+use crate::error::{AppError, AppResult};
+use chrono::format::{Item, Parsed, StrftimeItems};
+use chrono::{Datelike, NaiveDate};
+use std::fmt::Write;
+
+/// Windows 保留的设备名，不能作为文件夹名使用（含 0 号和上标数字的串口、并口）
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM0", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7",
+    "COM8", "COM9", "COM¹", "COM²", "COM³", "LPT0", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6",
+    "LPT7", "LPT8", "LPT9", "LPT¹", "LPT²", "LPT³",
+];
+
+/// 文件名中不允许出现的字符（含路径分隔符）
+const INVALID_CHARS: &[char] = &['/', '\\', '<', '>', ':', '"', '|', '?', '*'];
+
+/// 按模板渲染日期
+///
+/// 模板支持 strftime 风格的 `%` 指令（如 `%Y`、`%m`、`%d`、`%a`），
+/// 以及以下自定义变量：
+/// - `{date}`: 日期，如 `2024-03-15`；`{year}`、`{month}`、`{day}` 为其中各部分
+/// - `{weekday}`: 星期缩写，如 `Fri`
+/// - `{iso_week}`: ISO 周数，两位，如 `11`
+/// - `{quarter}`: 季度，`1`-`4`
+/// - `{day_of_year}`: 一年中的第几天，三位，如 `075`
+/// - `{hostname}`: 本机名称
+pub fn render(pattern: &str, date: &NaiveDate) -> AppResult<String> {
+    let mut output = String::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        format_strftime(&rest[..start], date, &mut output)?;
+
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| AppError::configuration("命名模板中的 '{' 没有对应的 '}'"))?;
+        output.push_str(&render_token(&after[..end], date)?);
+        rest = &after[end + 1..];
+    }
+    format_strftime(rest, date, &mut output)?;
+
+    Ok(output)
+}
+
+/// 按模板解析文件夹名，把其中的日期字段写入 `parsed`；名称与模板不符时返回 `false`
+///
+/// 多级目录可共用同一个 `parsed`，各级之间冲突的字段（如年份不同）视为不符。
+/// `{quarter}` 和 `{hostname}` 只被跳过，调用方应把得到的日期重新渲染后比对。
+pub fn parse_into(pattern: &str, name: &str, parsed: &mut Parsed) -> bool {
+    let mut input = name;
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            return false;
+        };
+        let Some(remainder) = parse_strftime(&rest[..start], input, parsed)
+            .and_then(|input| parse_token(&after[..end], input, parsed))
+        else {
+            return false;
+        };
+        input = remainder;
+        rest = &after[end + 1..];
+    }
+
+    parse_strftime(rest, input, parsed).is_some_and(str::is_empty)
+}
+
+/// 用于试渲染模板的两个相邻日期
+pub fn sample_dates() -> [NaiveDate; 2] {
+    [
+        NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 3, 16).unwrap(),
+    ]
+}
+
+/// 校验命名模板：能正确渲染，且渲染结果是合法的文件夹名
+pub fn validate_pattern(pattern: &str) -> AppResult<()> {
+    if pattern.trim().is_empty() {
+        return Err(AppError::invalid_path("命名模板不能为空"));
+    }
+
+    for date in sample_dates() {
+        validate_folder_name(&render(pattern, &date)?)?;
+    }
+
+    Ok(())
+}
+
+/// 校验单个文件夹名在各平台上都可用
+pub fn validate_folder_name(name: &str) -> AppResult<()> {
+    if name.trim().is_empty() {
+        return Err(AppError::invalid_path("文件夹名不能为空"));
+    }
+
+    if name == "." || name == ".." {
+        return Err(AppError::invalid_path(&format!("文件夹名不能为 \"{}\"", name)));
+    }
+
+    if let Some(c) = name.chars().find(|c| INVALID_CHARS.contains(c) || c.is_control()) {
+        return Err(AppError::invalid_path(&format!(
+            "文件夹名 \"{}\" 包含非法字符 '{}'",
+            name,
+            c.escape_default()
+        )));
+    }
+
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Err(AppError::invalid_path(&format!(
+            "文件夹名 \"{}\" 不能以点或空格结尾",
+            name
+        )));
+    }
+
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem)) {
+        return Err(AppError::invalid_path(&format!(
+            "文件夹名 \"{}\" 是系统保留名称",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+fn format_strftime(segment: &str, date: &NaiveDate, output: &mut String) -> AppResult<()> {
+    if segment.is_empty() {
+        return Ok(());
+    }
+
+    let items: Vec<Item> = StrftimeItems::new(segment).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(AppError::configuration(&format!(
+            "命名模板包含无效的日期格式: {}",
+            segment
+        )));
+    }
+
+    // 时间类指令（如 %H）无法用于日期，格式化时会失败
+    write!(output, "{}", date.format_with_items(items.into_iter())).map_err(|_| {
+        AppError::configuration(&format!("命名模板包含不支持的日期格式: {}", segment))
+    })
+}
+
+/// 替换自由文本中的 `{变量}`，不解释 `%` 指令，未知变量原样保留
+///
+/// 支持 [`render`] 中的全部变量，以及调用方通过 `extra` 传入的变量。
+/// 用于脚手架文件名和种子文件内容。
+pub fn substitute(text: &str, date: &NaiveDate, extra: &[(&str, &str)]) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+
+        let token = &after[..end];
+        let value = extra
+            .iter()
+            .find(|(name, _)| *name == token)
+            .map(|(_, value)| value.to_string())
+            .or_else(|| render_token(token, date).ok());
+
+        match value {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// 解析一段 strftime 格式，返回剩余的输入
+fn parse_strftime<'a>(segment: &str, input: &'a str, parsed: &mut Parsed) -> Option<&'a str> {
+    if segment.is_empty() {
+        return Some(input);
+    }
+    chrono::format::parse_and_remainder(parsed, input, StrftimeItems::new(segment)).ok()
+}
+
+/// 解析一个 `{变量}`，与 [`render_token`] 的输出对应
+fn parse_token<'a>(token: &str, input: &'a str, parsed: &mut Parsed) -> Option<&'a str> {
+    let segment = match token {
+        "date" => "%Y-%m-%d",
+        "year" => "%Y",
+        "month" => "%m",
+        "day" => "%d",
+        "weekday" => "%a",
+        "iso_week" => "%V",
+        "day_of_year" => "%j",
+        "quarter" => return input.strip_prefix(['1', '2', '3', '4']),
+        "hostname" => return input.strip_prefix(hostname().as_str()),
+        _ => return None,
+    };
+    parse_strftime(segment, input, parsed)
+}
+
+fn render_token(token: &str, date: &NaiveDate) -> AppResult<String> {
+    match token {
+        "date" => Ok(date.format("%Y-%m-%d").to_string()),
+        "year" => Ok(date.format("%Y").to_string()),
+        "month" => Ok(date.format("%m").to_string()),
+        "day" => Ok(date.format("%d").to_string()),
+        "weekday" => Ok(date.format("%a").to_string()),
+        "iso_week" => Ok(format!("{:02}", date.iso_week().week())),
+        "quarter" => Ok(((date.month0() / 3) + 1).to_string()),
+        "day_of_year" => Ok(format!("{:03}", date.ordinal())),
+        "hostname" => Ok(hostname()),
+        _ => Err(AppError::configuration(&format!(
+            "未知的模板变量: {{{}}}",
+            token
+        ))),
+    }
+}
+
+fn hostname() -> String {
+    gethostname::gethostname().to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+    }
+
+    #[test]
+    fn test_render_tokens() {
+        assert_eq!(render("%Y-%m-%d_{weekday}", &date()).unwrap(), "2024-03-15_Fri");
+        assert_eq!(render("%yQ{quarter}-W{iso_week}", &date()).unwrap(), "24Q1-W11");
+        assert_eq!(render("Daily-%Y%m%d", &date()).unwrap(), "Daily-20240315");
+        assert_eq!(render("%Y-{day_of_year}", &date()).unwrap(), "2024-075");
+    }
+
+    #[test]
+    fn test_parse_into() {
+        let parse = |pattern: &str, name: &str| {
+            let mut parsed = Parsed::new();
+            parse_into(pattern, name, &mut parsed)
+                .then(|| parsed.to_naive_date().ok())
+                .flatten()
+        };
+        assert_eq!(parse("%Y-%m-%d_{weekday}", "2024-03-15_Fri"), Some(date()));
+        assert_eq!(parse("Daily-%Y%m%d", "Daily-20240315"), Some(date()));
+        assert_eq!(parse("{year}Q{quarter}-{month}{day}", "2024Q1-0315"), Some(date()));
+        assert_eq!(parse("%Y-{day_of_year}", "2024-075"), Some(date()));
+        // 星期与日期不符、多余字符、缺少年份
+        assert_eq!(parse("%Y-%m-%d_{weekday}", "2024-03-15_Sat"), None);
+        assert_eq!(parse("%Y-%m-%d", "2024-03-15 copy"), None);
+        assert_eq!(parse("%m%d", "0315"), None);
+    }
+
+    #[test]
+    fn test_render_rejects_bad_patterns() {
+        assert!(render("{unknown}", &date()).is_err());
+        assert!(render("%Y-{weekday", &date()).is_err());
+        assert!(render("%Y%Q", &date()).is_err());
+        assert!(render("%Y-%H", &date()).is_err());
+    }
+
+    #[test]
+    fn test_substitute_keeps_unknown_text() {
+        let text = "# {date} {weekday}\n100% done {unknown} {folder_name} {";
+        assert_eq!(
+            substitute(text, &date(), &[("folder_name", "0315")]),
+            "# 2024-03-15 Fri\n100% done {unknown} 0315 {"
+        );
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("%Y-%m-%d_{weekday}").is_ok());
+        assert!(matches!(
+            validate_pattern("%Y/%m/%d").unwrap_err().error_type,
+            crate::error::ErrorType::InvalidPath
+        ));
+        assert!(validate_pattern("").is_err());
+    }
+
+    #[test]
+    fn test_validate_folder_name() {
+        assert!(validate_folder_name("2024-03-15").is_ok());
+        assert!(validate_folder_name("con").is_err());
+        assert!(validate_folder_name("LPT1.2024").is_err());
+        assert!(validate_folder_name("com0").is_err());
+        assert!(validate_folder_name("LPT0").is_err());
+        assert!(validate_folder_name("com²").is_err());
+        assert!(validate_folder_name("LPT³.txt").is_err());
+        assert!(validate_folder_name("COM10").is_ok());
+        assert!(validate_folder_name("a:b").is_err());
+        assert!(validate_folder_name("name.").is_err());
+        assert!(validate_folder_name("   ").is_err());
+    }
+}
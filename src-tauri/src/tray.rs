@@ -1,27 +1,30 @@
-use crate::folder::FolderManager;
+use crate::actions::Action;
+use crate::inbox;
 use crate::settings::AppSettings;
+use chrono::Local;
 use std::sync::Mutex;
 use tauri::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     Manager, Runtime,
 };
 
-pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
+const TRAY_ID: &str = "main-tray";
+const CREATE_PREFIX: &str = "create_now:";
+const OPEN_PREFIX: &str = "open_folder:";
 
-    let quit_i = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-    let show_i = MenuItem::with_id(app, "show", "显示设置", true, None::<&str>)?;
-    let create_i = MenuItem::with_id(app, "create_now", "立即创建", true, None::<&str>)?;
-    let open_i = MenuItem::with_id(app, "open_folder", "打开目录", true, None::<&str>)?;
-    let separator = PredefinedMenuItem::separator(app)?;
+pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
+    let settings = app
+        .state::<Mutex<AppSettings>>()
+        .lock()
+        .map(|settings| settings.clone())
+        .unwrap_or_default();
 
-    let menu = Menu::with_items(
-        app,
-        &[&create_i, &open_i, &separator, &show_i, &separator, &quit_i],
-    )?;
+    let menu = build_menu(app, &settings)?;
+    let tooltip = tooltip_text(&settings);
 
-    let _tray = TrayIconBuilder::with_id("main-tray")
-        .tooltip("Smart Folder Zen")
+    let _tray = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(tooltip)
         .icon(app.default_window_icon().unwrap().clone())
         .menu(&menu)
         .show_menu_on_left_click(false)
@@ -31,24 +34,27 @@ pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
                 cleanup_before_exit(app);
                 app.exit(0);
             }
+            "inbox_pause" => inbox::toggle_paused(app),
             "show" => {
                 if let Err(e) = show_settings_window(app) {
                     eprintln!("Failed to show settings: {:?}", e);
                 }
             }
-            "create_now" => {
-                if let Err(e) = create_folder_now(app) {
-                    eprintln!("Failed to create folder: {:?}", e);
-                    // TODO: Show notification to user
-                }
-            }
-            "open_folder" => {
-                if let Err(e) = open_current_folder(app) {
-                    eprintln!("Failed to open folder: {:?}", e);
+            id => {
+                let today = Local::now().date_naive();
+                let action = if let Some(profile_id) = id.strip_prefix(CREATE_PREFIX) {
+                    Action::CreateFolder { profile_id: Some(profile_id.to_string()), date: today }
+                } else if let Some(profile_id) = id.strip_prefix(OPEN_PREFIX) {
+                    Action::OpenFolder { profile_id: Some(profile_id.to_string()), date: today }
+                } else {
+                    return;
+                };
+
+                if let Err(e) = action.perform(app) {
+                    eprintln!("Tray action failed: {}", e);
                     // TODO: Show notification to user
                 }
             }
-            _ => {}
         })
         .on_tray_icon_event(|tray, event| {
             if let TrayIconEvent::Click {
@@ -58,7 +64,7 @@ pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
             } = event
             {
                 let app = tray.app_handle();
-                if let Err(e) = show_settings_window(&app) {
+                if let Err(e) = show_settings_window(app) {
                     eprintln!("Failed to show settings on tray click: {:?}", e);
                 }
             }
@@ -68,59 +74,95 @@ pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
     Ok(())
 }
 
-fn show_settings_window<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
-    if let Some(window) = app.get_webview_window("main") {
-        window.show()?;
-        window.set_focus()?;
-        window.unminimize()?;
+/// 按当前设置重建托盘菜单并刷新提示（如今天是否为休息日）
+pub fn refresh<R: Runtime>(app: &tauri::AppHandle<R>, settings: &AppSettings) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    match build_menu(app, settings) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                eprintln!("Failed to update tray menu: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to build tray menu: {}", e),
+    }
+
+    if let Err(e) = tray.set_tooltip(Some(tooltip_text(settings))) {
+        eprintln!("Failed to update tray tooltip: {}", e);
     }
-    Ok(())
 }
 
-fn create_folder_now<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<(), Box<dyn std::error::Error>> {
-    let settings_state = app.state::<Mutex<AppSettings>>();
-    let settings = settings_state.lock().map_err(|e| {
-        format!("Failed to lock settings: {}", e)
-    })?;
+/// 只有一个配置时直接列出操作，多个配置时每个配置一个子菜单
+fn build_menu<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    settings: &AppSettings,
+) -> tauri::Result<Menu<R>> {
+    let menu = Menu::new(app)?;
 
-    let result = FolderManager::create_today_folder(&settings);
-    
-    match result {
-        Ok(path) => {
-            println!("Successfully created folder: {}", path);
-            // TODO: Show success notification
-        }
-        Err(e) => {
-            eprintln!("Failed to create folder: {}", e);
-            // TODO: Show error notification
+    for profile in &settings.profiles {
+        let create_i = MenuItem::with_id(
+            app,
+            format!("{}{}", CREATE_PREFIX, profile.id),
+            "立即创建",
+            true,
+            None::<&str>,
+        )?;
+        let open_i = MenuItem::with_id(
+            app,
+            format!("{}{}", OPEN_PREFIX, profile.id),
+            "打开目录",
+            true,
+            None::<&str>,
+        )?;
+
+        if settings.profiles.len() == 1 {
+            menu.append_items(&[&create_i, &open_i])?;
+        } else {
+            let submenu = Submenu::with_items(app, &profile.name, true, &[&create_i, &open_i])?;
+            menu.append(&submenu)?;
         }
     }
 
-    Ok(())
-}
-
-fn open_current_folder<R: Runtime>(app: &tauri::AppHandle<R>) -> Result<(), Box<dyn std::error::Error>> {
-    let settings_state = app.state::<Mutex<AppSettings>>();
-    let settings = settings_state.lock().map_err(|e| {
-        format!("Failed to lock settings: {}", e)
-    })?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    if settings.inbox.enabled {
+        let pause_i = CheckMenuItem::with_id(
+            app,
+            "inbox_pause",
+            "暂停收件箱归档",
+            true,
+            inbox::is_paused(),
+            None::<&str>,
+        )?;
+        menu.append_items(&[&separator, &pause_i])?;
+    }
 
-    let folder_path = if FolderManager::today_folder_exists(&settings) {
-        FolderManager::get_today_folder_path(&settings)
-    } else {
-        settings.folder_path.clone()
-    };
+    let quit_i = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+    let show_i = MenuItem::with_id(app, "show", "显示设置", true, None::<&str>)?;
+    menu.append_items(&[&separator, &show_i, &separator, &quit_i])?;
 
-    drop(settings); // Release the lock
+    Ok(menu)
+}
 
-    match FolderManager::open_folder_in_explorer(&folder_path) {
-        Ok(_) => println!("Successfully opened folder: {}", folder_path),
-        Err(e) => {
-            eprintln!("Failed to open folder: {}", e);
-            // TODO: Show error notification
-        }
+fn tooltip_text(settings: &AppSettings) -> String {
+    let today = Local::now().date_naive();
+    let mut text = "Smart Folder Zen".to_string();
+    if !settings.calendar.allows_auto_create(&today) {
+        text.push_str("\n今天是休息日，不会自动创建文件夹");
     }
+    if settings.inbox.enabled && inbox::is_paused() {
+        text.push_str("\n收件箱归档已暂停");
+    }
+    text
+}
 
+fn show_settings_window<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
+    if let Some(window) = app.get_webview_window("main") {
+        window.show()?;
+        window.set_focus()?;
+        window.unminimize()?;
+    }
     Ok(())
 }
 
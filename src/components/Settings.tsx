@@ -22,7 +22,8 @@ import type { InputProps, RadioGroupProps } from '@fluentui/react-components';
 import { FolderRegular, CalendarRegular, SaveRegular, ArrowResetRegular, CheckmarkCircleRegular, ErrorCircleRegular } from '@fluentui/react-icons';
 import { open } from '@tauri-apps/plugin-dialog';
 import { invoke } from '@tauri-apps/api/core';
-import { AppSettings } from '../types/settings';
+import { listen } from '@tauri-apps/api/event';
+import { AppError, AppSettings, FolderCreatedEvent, InboxFileMovedEvent, Profile, StartupDiagnostics } from '../types/settings';
 
 const useStyles = makeStyles({
   container: {
@@ -225,12 +226,25 @@ const Settings: React.FC = () => {
   const { dispatchToast } = useToastController(toasterId);
 
   const [settings, setSettings] = React.useState<AppSettings>({
-    folder_path: '',
-    date_format: 'YYYYMMDD',
-    auto_start: true, // 默认启用，不可更改
-    auto_create_on_startup: true // 默认启用，不可更改
+    profiles: [{
+      id: 'default',
+      name: '默认',
+      folder_path: '',
+      date_format: 'YYYYMMDD',
+      auto_create: true // 默认启用，不可更改
+    }],
+    auto_start: true // 默认启用，不可更改
   });
 
+  // 界面目前只编辑默认配置
+  const profile = settings.profiles[0];
+  const updateProfile = (patch: Partial<Profile>) => {
+    setSettings(prev => ({
+      ...prev,
+      profiles: prev.profiles.map((p, index) => index === 0 ? { ...p, ...patch } : p)
+    }));
+  };
+
   const [isLoading, setIsLoading] = React.useState(false);
 
   const showToast = (title: string, message: string, intent: 'success' | 'error' | 'info' = 'info') => {
@@ -262,6 +276,67 @@ const Settings: React.FC = () => {
     loadSettings();
   }, []);
 
+  // 提示启动时的配置恢复情况
+  React.useEffect(() => {
+    invoke<StartupDiagnostics>('get_startup_diagnostics')
+      .then(({ config_recovery }) => {
+        if (config_recovery) {
+          const detail = config_recovery.restored_from ? '已从备份恢复' : '已重置为默认设置';
+          showToast('配置文件已损坏', `${detail}，原文件保存在 ${config_recovery.quarantined_path}`, 'error');
+        }
+      })
+      .catch(err => console.error('获取启动诊断失败:', err));
+  }, []);
+
+  // 后台调度器自动创建文件夹时提示
+  React.useEffect(() => {
+    const unlisten = listen<FolderCreatedEvent>('folder-created', (event) => {
+      showToast('文件夹已创建', event.payload.path, 'success');
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // 配置文件被外部修改后同步到界面
+  React.useEffect(() => {
+    const unlistenChanged = listen<AppSettings>('settings-changed', (event) => {
+      setSettings(event.payload);
+      showToast('设置已更新', '配置文件已在外部修改并生效', 'info');
+    });
+    const unlistenFailed = listen<AppError>('settings-reload-failed', (event) => {
+      showToast('配置文件无效', `外部修改未生效: ${event.payload.message}`, 'error');
+    });
+
+    return () => {
+      unlistenChanged.then(fn => fn());
+      unlistenFailed.then(fn => fn());
+    };
+  }, []);
+
+  // 收件箱归档移动文件时提示
+  React.useEffect(() => {
+    const unlisten = listen<InboxFileMovedEvent>('inbox-file-moved', (event) => {
+      showToast('文件已归档', event.payload.to, 'success');
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
+  // smartfolderzen:// 链接无效或执行失败时提示
+  React.useEffect(() => {
+    const unlisten = listen<AppError>('deep-link-failed', (event) => {
+      showToast('链接无法打开', event.payload.message, 'error');
+    });
+
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, []);
+
   const handleSelectFolder = async () => {
     setIsLoading(true);
     try {
@@ -272,7 +347,7 @@ const Settings: React.FC = () => {
       });
       
       if (selectedPath && typeof selectedPath === 'string') {
-        setSettings(prev => ({ ...prev, folder_path: selectedPath }));
+        updateProfile({ folder_path: selectedPath });
         showToast('文件夹已选择', '路径设置成功', 'success');
       }
     } catch (err) {
@@ -283,7 +358,7 @@ const Settings: React.FC = () => {
   };
 
   const handleSave = async () => {
-    if (!settings.folder_path) {
+    if (!profile.folder_path) {
       showToast('保存失败', '请先选择文件夹路径', 'error');
       return;
     }
@@ -300,20 +375,19 @@ const Settings: React.FC = () => {
   };
 
   const handleReset = () => {
-    setSettings(prev => ({
-      ...prev,
+    updateProfile({
       folder_path: '',
       date_format: 'YYYYMMDD',
-    }));
+    });
     showToast('重置成功', '设置已恢复到默认值', 'success');
   };
 
   const handlePathChange: InputProps['onChange'] = (event, data) => {
-    setSettings(prev => ({ ...prev, folder_path: data.value }));
+    updateProfile({ folder_path: data.value });
   };
 
   const handleDateFormatChange = (format: 'MMDD' | 'YYYYMMDD') => {
-    setSettings(prev => ({ ...prev, date_format: format }));
+    updateProfile({ date_format: format });
   };
 
   const getCurrentDatePreview = (format: string) => {
@@ -346,7 +420,7 @@ const Settings: React.FC = () => {
             <div className={classes.inputContainer}>
               <Input
                 id={folderPathId}
-                value={settings.folder_path}
+                value={profile.folder_path}
                 onChange={handlePathChange}
                 placeholder="E:\Tmp\inbox"
                 disabled={isLoading}
@@ -373,18 +447,18 @@ const Settings: React.FC = () => {
             </div>
             <div className={classes.formatCardsContainer}>
               <div 
-                className={`${classes.formatCard} ${settings.date_format === 'MMDD' ? classes.formatCardSelected : ''}`}
+                className={`${classes.formatCard} ${profile.date_format === 'MMDD' ? classes.formatCardSelected : ''}`}
                 onClick={() => handleDateFormatChange('MMDD')}
               >
-                <span className={classes.formatCardIcon}>{settings.date_format === 'MMDD' ? '◉' : '○'}</span>
+                <span className={classes.formatCardIcon}>{profile.date_format === 'MMDD' ? '◉' : '○'}</span>
                 <div className={classes.formatCardTitle}>MMDD</div>
                 <div className={classes.formatCardPreview}>预览: {getCurrentDatePreview('MMDD')}</div>
               </div>
               <div 
-                className={`${classes.formatCard} ${settings.date_format === 'YYYYMMDD' ? classes.formatCardSelected : ''}`}
+                className={`${classes.formatCard} ${profile.date_format === 'YYYYMMDD' ? classes.formatCardSelected : ''}`}
                 onClick={() => handleDateFormatChange('YYYYMMDD')}
               >
-                <span className={classes.formatCardIcon}>{settings.date_format === 'YYYYMMDD' ? '◉' : '○'}</span>
+                <span className={classes.formatCardIcon}>{profile.date_format === 'YYYYMMDD' ? '◉' : '○'}</span>
                 <div className={classes.formatCardTitle}>YYYYMMDD</div>
                 <div className={classes.formatCardPreview}>预览: {getCurrentDatePreview('YYYYMMDD')}</div>
               </div>
@@ -1,8 +1,16 @@
 import React, { useState } from 'react';
-import { AppSettings } from '../types/settings';
+import { DateFormat } from '../types/settings';
+
+// 简化版原型只在本地维护状态，不与后端同步
+interface SimpleSettings {
+  folder_path: string;
+  date_format: DateFormat;
+  auto_start: boolean;
+  auto_create_on_startup: boolean;
+}
 
 const SettingsSimpleCSS: React.FC = () => {
-  const [settings, setSettings] = useState<AppSettings>({
+  const [settings, setSettings] = useState<SimpleSettings>({
     folder_path: '',
     date_format: 'YYYYMMDD',
     auto_start: false,
@@ -30,7 +38,7 @@ const SettingsSimpleCSS: React.FC = () => {
   };
 
   const handleReset = () => {
-    setSettings((prev: AppSettings) => ({
+    setSettings((prev: SimpleSettings) => ({
       ...prev,
       folder_path: '',
       date_format: 'YYYYMMDD',
@@ -41,19 +49,19 @@ const SettingsSimpleCSS: React.FC = () => {
   };
 
   const handleFormatChange = (format: 'MMDD' | 'YYYYMMDD') => {
-    setSettings((prev: AppSettings) => ({ ...prev, date_format: format }));
+    setSettings((prev: SimpleSettings) => ({ ...prev, date_format: format }));
   };
 
   const handlePathChange = (e: React.ChangeEvent<HTMLInputElement>) => {
-    setSettings((prev: AppSettings) => ({ ...prev, folder_path: e.target.value }));
+    setSettings((prev: SimpleSettings) => ({ ...prev, folder_path: e.target.value }));
   };
 
   const handleAutoStartChange = (e: React.ChangeEvent<HTMLInputElement>) => {
-    setSettings((prev: AppSettings) => ({ ...prev, auto_start: e.target.checked }));
+    setSettings((prev: SimpleSettings) => ({ ...prev, auto_start: e.target.checked }));
   };
 
   const handleAutoCreateChange = (e: React.ChangeEvent<HTMLInputElement>) => {
-    setSettings((prev: AppSettings) => ({ ...prev, auto_create_on_startup: e.target.checked }));
+    setSettings((prev: SimpleSettings) => ({ ...prev, auto_create_on_startup: e.target.checked }));
   };
 
   return (
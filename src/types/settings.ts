@@ -1,6 +1,329 @@
-export interface AppSettings {
+// 自定义模板序列化为 { Custom: "%Y-%m-%d_{weekday}" }
+export type DateFormat = 'MMDD' | 'YYYYMMDD' | { Custom: string };
+
+// 日期文件夹内的脚手架，名称和内容支持 {date}、{weekday} 等变量
+export type ScaffoldEntry =
+  | { type: 'dir'; name: string; children?: ScaffoldEntry[] }
+  | { type: 'file'; name: string; content?: string };
+
+// 工作日策略，日期格式为 "2024-10-01"
+export interface CalendarPolicy {
+  workdays_only: boolean;
+  workdays: Array<'Mon' | 'Tue' | 'Wed' | 'Thu' | 'Fri' | 'Sat' | 'Sun'>;
+  holidays: string[];
+  extra_workdays: string[];
+}
+
+// 一组独立的日期文件夹配置，如“工作”和“个人”
+export interface Profile {
+  id: string;
+  name: string;
+  folder_path: string;
+  date_format: DateFormat;
+  // 分层目录模板，如 [{ Custom: '%Y' }, { Custom: '%m' }, { Custom: '%d' }]
+  folder_levels?: DateFormat[];
+  scaffold?: ScaffoldEntry[];
+  auto_create: boolean;
+  // 新建日期文件夹后执行的命令
+  hooks?: Hook[];
+}
+
+export type HookFailurePolicy = 'ignore' | 'warn' | 'rollback';
+
+// 命令可读取环境变量 SFZ_FOLDER_PATH、SFZ_DATE、SFZ_PROFILE_ID、SFZ_PROFILE_NAME
+export interface Hook {
+  name?: string;
+  command: string;
+  // 为空时为新建的文件夹
+  working_dir?: string | null;
+  timeout_secs?: number;
+  on_failure?: HookFailurePolicy;
+}
+
+// get_hook_log 的返回值，最新的在前
+export interface HookRun {
+  hook: string;
+  profile_id: string;
   folder_path: string;
-  date_format: 'MMDD' | 'YYYYMMDD';
+  date: string;
+  started_at: string;
+  duration_ms: number;
+  success: boolean;
+  exit_code: number | null;
+  timed_out: boolean;
+  stdout: string;
+  stderr: string;
+  on_failure: HookFailurePolicy;
+}
+
+export interface AppSettings {
+  // 配置文件结构版本，由后端维护
+  version?: number;
+  // 第一个为默认配置
+  profiles: Profile[];
   auto_start: boolean;
-  auto_create_on_startup: boolean;
-}
\ No newline at end of file
+  auto_create_daily?: boolean;
+  // 每日定时，如 "08:30:00"
+  auto_create_time?: string | null;
+  calendar?: CalendarPolicy;
+  // 本地控制接口，默认关闭
+  control_api?: {
+    enabled: boolean;
+    port: number;
+  };
+  inbox?: InboxSettings;
+  // 归档规则，按顺序评估
+  rules?: Rule[];
+  retention?: RetentionSettings;
+}
+
+// 定期归档早于保留天数的日期文件夹
+export interface RetentionSettings {
+  enabled: boolean;
+  // 每天执行的时间，如 "03:00:00"
+  run_at: string;
+  older_than_days: number;
+  archive_root: string;
+  // 压缩模式在压缩后删除原文件夹，不能撤销
+  mode: 'move' | 'zip_per_folder' | 'zip_per_month';
+  // 空文件夹直接移入回收站
+  trash_empty: boolean;
+  // 为空时处理全部配置
+  profile_ids: string[];
+}
+
+export type RetentionAction =
+  | { type: 'move'; to: string }
+  | { type: 'zip'; archive: string; entry: string }
+  | { type: 'trash' };
+
+// plan_retention 的返回值
+export interface RetentionPlan {
+  cutoff: string;
+  // kind 为 inferred_year 时名称中没有年份，date 的年份按修改时间推断
+  items: {
+    profile_id: string;
+    date: string;
+    path: string;
+    relative: string;
+    kind: 'day' | 'inferred_year';
+    action: RetentionAction;
+  }[];
+}
+
+// run_retention 的返回值，也是 retention-finished 事件的负载
+export interface RetentionReport {
+  archived: {
+    at: string;
+    profile_id: string;
+    date: string;
+    source: string;
+    action: RetentionAction;
+    files: number;
+    bytes: number;
+  }[];
+  failed: { path: string; reason: string }[];
+}
+
+// scan_folders 找到的日期文件夹；inferred_year 表示名称中没有年份，年份是推断的
+export interface CatalogEntry {
+  date: string;
+  path: string;
+  kind: 'day' | 'inferred_year';
+}
+
+// 某一天的标签和备注，保存在当天文件夹的 .smartfolderzen.json 中
+export interface DayMeta {
+  tags: string[];
+  note: string;
+}
+
+// get_month_overview 中每天一项
+export interface DayOverview extends DayMeta {
+  date: string;
+  exists: boolean;
+  path: string | null;
+  file_count: number;
+  total_bytes: number;
+  last_modified: string | null;
+  is_workday: boolean;
+}
+
+export interface MonthOverview {
+  profile_id: string;
+  year: number;
+  month: number;
+  days: DayOverview[];
+}
+
+// open_date 中要打开的日期还没有文件夹时的处理方式
+export type MissingFolderPolicy = 'nearest' | 'create' | 'error';
+
+// open_date 的返回值
+export interface OpenedDate {
+  date: string;
+  path: string;
+}
+
+// 保存设置后命名格式变化时发出的 naming-changed 事件负载
+export interface NamingChangedEvent {
+  profile_id: string;
+  from_levels: DateFormat[];
+}
+
+// 把旧命名格式的文件夹改为当前格式
+export interface RenameOptions {
+  profile_id?: string | null;
+  from_levels: DateFormat[];
+  // 旧格式缺少年份时使用的年份，为空时按修改时间推断
+  year?: number | null;
+  // 是否也执行年份靠推断得出的重命名
+  include_ambiguous?: boolean;
+}
+
+export interface RenamePlan {
+  renames: { from: string; to: string; date: string; ambiguity: string | null }[];
+  skipped: { path: string; reason: string }[];
+}
+
+export interface RenameReport {
+  operation_id: string | null;
+  renamed: number;
+  failed: { path: string; reason: string }[];
+  // 文件夹已重命名但没能写入操作日志的原因，此时不能撤销
+  journal_error: string | null;
+}
+
+// 把下载目录等来源中的新文件移入今天的文件夹
+export interface InboxSettings {
+  enabled: boolean;
+  sources: string[];
+  // 为空时使用默认配置
+  profile_id?: string | null;
+  stable_secs?: number;
+  ignored_extensions?: string[];
+  collision?: 'number' | 'timestamp' | 'skip';
+}
+
+// 归档规则：所有已设置的条件都满足时匹配
+export interface Rule {
+  name: string;
+  extensions?: string[];
+  glob?: string | null;
+  regex?: string | null;
+  min_size?: number | null;
+  max_size?: number | null;
+  source_dir?: string | null;
+  action: RuleAction;
+  // 匹配后停止（默认）或继续评估后续规则
+  on_match?: 'stop' | 'continue';
+}
+
+export type RuleAction =
+  | { type: 'move_to'; subfolder: string }
+  | { type: 'ignore' };
+
+// test_rules 命令的返回值
+export interface RuleTestResult {
+  evaluation: {
+    // [规则序号, 规则名称]
+    matched: [number, string][];
+    outcome: { type: 'move'; subfolder: string } | { type: 'ignore' };
+  };
+  destination: string | null;
+}
+
+// 按日期整理（plan_organize / run_organize）的参数
+export interface OrganizeOptions {
+  source_dir: string;
+  profile_id?: string | null;
+  // 依次尝试的日期来源，默认 ['metadata', 'file_name', 'modified']
+  date_sources?: ('metadata' | 'file_name' | 'modified' | 'created')[];
+  collision?: 'number' | 'timestamp' | 'skip';
+}
+
+export interface SkippedFile {
+  path: string;
+  reason: string;
+}
+
+export interface OrganizePlan {
+  moves: { from: string; to: string; date: string }[];
+  skipped: SkippedFile[];
+}
+
+// run_organize 执行时发出的 organize-progress 事件
+export interface OrganizeProgressEvent {
+  done: number;
+  total: number;
+  file: string;
+}
+
+export interface OrganizeReport {
+  // 操作日志中的 ID，传给 undo_operation 撤销整次整理
+  operation_id: string | null;
+  moved: number;
+  failed: SkippedFile[];
+  // 文件已移动但没能写入操作日志的原因，此时不能撤销
+  journal_error: string | null;
+}
+
+// 文件的大小和修改时间，撤销前据此判断文件是否被改过
+export interface Fingerprint {
+  len: number;
+  modified: string | null;
+}
+
+export type JournalEntry =
+  | { type: 'create'; path: string; at: string; fingerprint: Fingerprint | null }
+  | { type: 'move' | 'rename'; from: string; to: string; at: string; fingerprint: Fingerprint | null }
+  | { type: 'remove'; path: string; at: string };
+
+// 操作日志中的一次操作（list_operations / undo_operation / undo_last）
+export interface Operation {
+  id: string;
+  kind:
+    | { type: 'create_folder'; profile_id: string; date: string }
+    | { type: 'inbox' }
+    | { type: 'organize'; source_dir: string }
+    | { type: 'retention' }
+    | { type: 'rename_folders'; profile_id: string }
+    | { type: 'undo'; operation_id: string };
+  at: string;
+  entries: JournalEntry[];
+}
+
+// 收件箱归档发出的 inbox-file-moved 事件
+export interface InboxFileMovedEvent {
+  from: string;
+  to: string;
+}
+
+// 后台调度器发出的 folder-created 事件
+export interface FolderCreatedEvent {
+  profile_id: string;
+  path: string;
+  trigger: 'day_changed' | 'scheduled_time';
+}
+
+export interface AppError {
+  message: string;
+  error_type: 'FileSystem' | 'InvalidPath' | 'PermissionDenied' | 'Configuration' | 'Unknown';
+}
+
+// list_settings_backups 的返回值，index 越大越旧
+export interface SettingsBackup {
+  index: number;
+  path: string;
+  modified: string | null;
+}
+
+// get_startup_diagnostics 的返回值
+export interface StartupDiagnostics {
+  config_recovery: {
+    error: AppError;
+    quarantined_path: string;
+    restored_from: string | null;
+  } | null;
+  config_error: AppError | null;
+}